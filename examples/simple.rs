@@ -1,6 +1,7 @@
 // First, create a `logos` lexer:
 
 #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+#[logos(skip r"[ \t\n\f]+")]
 enum Token {
     #[token("+")]
     Plus,
@@ -8,12 +9,8 @@ enum Token {
     #[token("-")]
     Minus,
 
-    #[regex(r"-?[0-9]+", |lex| lex.slice().parse())]
+    #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
     Number(i64),
-
-    #[error]
-    #[regex(r"[ \t\n\f]+", logos::skip)]
-    Error,
 }
 
 // Then, write a nom parser that accepts a `Tokens<'_, Token>` as input:
@@ -0,0 +1,557 @@
+//! Input types that store the lexed tokens in a buffer.
+
+use alloc::rc::Rc;
+use core::{
+    fmt, iter,
+    ops::{Range, RangeFrom, RangeFull, RangeTo},
+    slice,
+};
+
+use logos::{Logos, Span};
+use nom::{
+    error::{ErrorKind, ParseError},
+    Compare, CompareResult, IResult, InputIter, InputLength, InputTake, InputTakeAtPosition,
+    Offset, Slice,
+};
+
+use crate::Tokens;
+
+/// A nom input that lexes the whole source once and stores the tokens in a shared buffer.
+///
+/// [`Tokens`] lexes lazily, so cloning it copies the lexer, and looking ahead or measuring the
+/// input lexes the same tokens again. This is slow for grammars that backtrack a lot. A
+/// `BufferedTokens` is a cursor into a reference-counted buffer instead: cloning, peeking and
+/// slicing are O(1).
+///
+/// Lengths and offsets used by the nom traits are measured in tokens. Hooks configured on a
+/// [`Tokens`] (like [`Tokens::with_trivia_filter`] or [`Tokens::with_layout`]) are applied when
+/// converting it with [`From`].
+///
+/// Only string sources are supported: the source and the slices of the tokens are `&str`. To
+/// parse a byte source, use [`Tokens`].
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::BufferedTokens;
+/// use nom::{bytes::complete::tag, multi::many0, sequence::preceded, IResult};
+///
+/// type Input<'i> = BufferedTokens<'i, Token>;
+///
+/// fn number(input: Input<'_>) -> IResult<Input<'_>, i64> {
+///     match input.clone().next_token() {
+///         (rest, Some((Token::Number(n), _, _))) => Ok((rest, n)),
+///         _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Digit))),
+///     }
+/// }
+///
+/// fn sum(input: Input<'_>) -> IResult<Input<'_>, i64> {
+///     let (input, first) = number(input)?;
+///     let (input, rest) = many0(preceded(tag(&[Token::Plus][..]), number))(input)?;
+///     Ok((input, first + rest.iter().sum::<i64>()))
+/// }
+///
+/// let tokens = BufferedTokens::new("1 + 2 + 3");
+/// assert_eq!(tokens.len(), 5);
+///
+/// let (rest, result) = sum(tokens).unwrap();
+/// assert_eq!(result, 6);
+/// assert!(rest.is_empty());
+/// ```
+pub struct BufferedTokens<'i, T> {
+    source: &'i str,
+    tokens: Rc<[(T, Span)]>,
+    /// The index of the next token
+    start: usize,
+    /// The index after the last token
+    end: usize,
+    /// The span of the lexing error at which the buffered tokens end
+    lex_error: Option<Span>,
+}
+
+impl<'i, T> BufferedTokens<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Default + Clone,
+{
+    /// Lexes `source` and stores all tokens.
+    pub fn new(source: &'i str) -> Self {
+        Tokens::new(source).into()
+    }
+}
+
+impl<'i, T> From<Tokens<'i, T>> for BufferedTokens<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    /// Lexes the remaining tokens of `tokens` and stores them. If the tokens stop at a lexing
+    /// error, it is available from [`BufferedTokens::lex_error`].
+    fn from(tokens: Tokens<'i, T>) -> Self {
+        let source = tokens.source();
+        let mut iter = tokens.into_iter();
+        let tokens: Rc<[(T, Span)]> = iter.by_ref().map(|(t, span, _)| (t, span)).collect();
+        BufferedTokens {
+            source,
+            end: tokens.len(),
+            tokens,
+            start: 0,
+            lex_error: iter.tokens.lex_error().map(|(_, span)| span),
+        }
+    }
+}
+
+impl<T> BufferedTokens<'_, T> {
+    /// Returns the span of the lexing error at the current position, if any.
+    ///
+    /// Like [`Tokens`], the buffer ends before source text that the lexer can't recognize (see
+    /// [`Tokens::lex_error`]). This returns the span of that text once all buffered tokens were
+    /// consumed.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::BufferedTokens;
+    ///
+    /// let tokens = BufferedTokens::<Token>::new("1 + $ 2");
+    /// assert_eq!(tokens.len(), 2);
+    /// assert_eq!(tokens.lex_error(), None);
+    ///
+    /// let rest = tokens.advance().advance();
+    /// assert_eq!(rest.lex_error(), Some(4..5));
+    /// ```
+    pub fn lex_error(&self) -> Option<Span> {
+        if self.start < self.tokens.len() {
+            return None;
+        }
+        self.lex_error.clone()
+    }
+}
+
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    /// Lexes the remaining input and returns a [`BufferedTokens`], whose clones share one
+    /// reference-counted buffer and only carry a cursor. Cloning it is a pointer copy, which
+    /// makes heavily backtracking grammars much cheaper.
+    ///
+    /// The configured hooks are applied while lexing, so this can also be used after
+    /// [`Tokens::with_layout`] or [`Tokens::with_trivia_filter`].
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 2").advance().buffered();
+    /// let copy = tokens.clone().advance();
+    ///
+    /// assert_eq!(tokens.peek(), Some((Token::Plus, "+")));
+    /// assert_eq!(copy.peek(), Some((Token::Number(2), "2")));
+    /// ```
+    pub fn buffered(self) -> BufferedTokens<'i, T> {
+        self.into()
+    }
+}
+
+/// A nom input over a slice of tokens and their spans that were lexed elsewhere, for example in
+/// an earlier compiler phase.
+///
+/// It works like [`BufferedTokens`], but borrows the tokens instead of owning them. The spans
+/// must refer to `source`, which is a `str` like the source of a `BufferedTokens`.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos::Logos;
+/// use logos_nom_bridge::TokenSlice;
+/// use nom::{bytes::complete::tag, IResult};
+///
+/// let source = "1 + 2";
+/// let tokens: Vec<_> = Token::lexer(source)
+///     .spanned()
+///     .map(|(token, span)| (token.unwrap(), span))
+///     .collect();
+///
+/// let input = TokenSlice::new(source, &tokens).advance();
+/// let result: IResult<_, _> = tag(&[Token::Plus][..])(input);
+/// let (rest, plus) = result.unwrap();
+///
+/// assert_eq!(plus.as_slice(), [(Token::Plus, 2..3)]);
+/// assert_eq!(rest.peek(), Some((Token::Number(2), "2")));
+/// ```
+pub struct TokenSlice<'i, T> {
+    source: &'i str,
+    tokens: &'i [(T, Span)],
+    /// The index of the next token
+    start: usize,
+    /// The index after the last token
+    end: usize,
+}
+
+impl<'i, T> TokenSlice<'i, T> {
+    /// Creates an input over `tokens`, whose spans refer to `source`.
+    pub fn new(source: &'i str, tokens: &'i [(T, Span)]) -> Self {
+        TokenSlice {
+            source,
+            tokens,
+            start: 0,
+            end: tokens.len(),
+        }
+    }
+}
+
+/// Implements the inherent methods and nom traits shared by the buffered input types. They
+/// have the fields `source`, `tokens`, `start` and `end`, where `tokens` dereferences to a slice,
+/// and implement `Clone`.
+macro_rules! impl_token_buffer {
+    ($ty:ident, $elements:ty, |$this:ident| $iter_elements:expr) => {
+        impl<'i, T: Clone> $ty<'i, T> {
+            /// Returns the source string, including the parts that were already consumed.
+            pub fn source(&self) -> &'i str {
+                self.source
+            }
+
+            /// Returns the remaining tokens with their spans.
+            pub fn as_slice(&self) -> &[(T, Span)] {
+                &self.tokens[self.start..self.end]
+            }
+
+            /// Returns the number of remaining tokens.
+            pub fn len(&self) -> usize {
+                self.end - self.start
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// Returns an iterator over the remaining tokens, without consuming them.
+            pub fn iter(&self) -> impl Iterator<Item = (T, Span, &'i str)> + '_ {
+                let source = self.source;
+                self.as_slice()
+                    .iter()
+                    .map(move |(t, span)| (t.clone(), span.clone(), &source[span.clone()]))
+            }
+
+            pub fn peek(&self) -> Option<(T, &'i str)> {
+                self.peek_nth(0)
+            }
+
+            /// Returns the token `n` positions ahead without consuming anything.
+            pub fn peek_nth(&self, n: usize) -> Option<(T, &'i str)> {
+                let (t, span) = self.as_slice().get(n)?;
+                Some((t.clone(), &self.source[span.clone()]))
+            }
+
+            /// Returns the next token together with its span and source slice, without
+            /// consuming it.
+            pub fn peek_with_span(&self) -> Option<(T, Span, &'i str)> {
+                let (t, span) = self.as_slice().first()?;
+                Some((t.clone(), span.clone(), &self.source[span.clone()]))
+            }
+
+            pub fn advance(self) -> Self {
+                self.next_token().0
+            }
+
+            /// Consumes the next token and returns it together with its span and source slice.
+            pub fn next_token(mut self) -> (Self, Option<(T, Span, &'i str)>) {
+                let token = self.peek_with_span();
+                if token.is_some() {
+                    self.start += 1;
+                }
+                (self, token)
+            }
+
+            /// Returns a view of the tokens in the given range of indices, relative to the current
+            /// position
+            fn window(&self, start: usize, end: usize) -> Self {
+                assert!(
+                    start <= end && end <= self.len(),
+                    "token index out of bounds"
+                );
+                let mut window = self.clone();
+                window.start = self.start + start;
+                window.end = self.start + end;
+                window
+            }
+        }
+
+        /// Two inputs are equal if they view the same buffer, not just an equal one, at the same
+        /// position. Like for [`Tokens`], this takes constant time.
+        impl<T> PartialEq for $ty<'_, T> {
+            fn eq(&self, other: &Self) -> bool {
+                core::ptr::eq(&self.tokens[..], &other.tokens[..])
+                    && self.start == other.start
+                    && self.end == other.end
+            }
+        }
+
+        impl<T> Eq for $ty<'_, T> {}
+
+        impl<T: fmt::Debug> fmt::Debug for $ty<'_, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_tuple(stringify!($ty))
+                    .field(&&self.tokens[self.start..self.end])
+                    .finish()
+            }
+        }
+
+        impl<'i, T: Clone> InputIter for $ty<'i, T> {
+            type Item = (T, Span);
+
+            type Iter = iter::Enumerate<$elements>;
+
+            type IterElem = $elements;
+
+            fn iter_indices(&self) -> Self::Iter {
+                self.iter_elements().enumerate()
+            }
+
+            fn iter_elements(&self) -> Self::IterElem {
+                let $this = self;
+                $iter_elements
+            }
+
+            fn position<P>(&self, predicate: P) -> Option<usize>
+            where
+                P: Fn(Self::Item) -> bool,
+            {
+                self.as_slice()
+                    .iter()
+                    .position(|item| predicate(item.clone()))
+            }
+
+            fn slice_index(&self, count: usize) -> Result<usize, nom::Needed> {
+                match count.checked_sub(self.len()) {
+                    Some(missing) if missing > 0 => Err(nom::Needed::new(missing)),
+                    _ => Ok(count),
+                }
+            }
+        }
+
+        impl<T: Clone> InputLength for $ty<'_, T> {
+            fn input_len(&self) -> usize {
+                self.len()
+            }
+        }
+
+        impl<T: Clone> InputTake for $ty<'_, T> {
+            fn take(&self, count: usize) -> Self {
+                self.window(0, count)
+            }
+
+            fn take_split(&self, count: usize) -> (Self, Self) {
+                (self.window(count, self.len()), self.window(0, count))
+            }
+        }
+
+        impl<T: Clone> Offset for $ty<'_, T> {
+            fn offset(&self, second: &Self) -> usize {
+                second.start - self.start
+            }
+        }
+
+        impl<T: Clone> Slice<Range<usize>> for $ty<'_, T> {
+            fn slice(&self, range: Range<usize>) -> Self {
+                self.window(range.start, range.end)
+            }
+        }
+
+        impl<T: Clone> Slice<RangeTo<usize>> for $ty<'_, T> {
+            fn slice(&self, range: RangeTo<usize>) -> Self {
+                self.window(0, range.end)
+            }
+        }
+
+        impl<T: Clone> Slice<RangeFrom<usize>> for $ty<'_, T> {
+            fn slice(&self, range: RangeFrom<usize>) -> Self {
+                self.window(range.start, self.len())
+            }
+        }
+
+        impl<T: Clone> Slice<RangeFull> for $ty<'_, T> {
+            fn slice(&self, _: RangeFull) -> Self {
+                self.clone()
+            }
+        }
+
+        impl<'a, T: PartialEq + Clone> Compare<&'a [T]> for $ty<'_, T> {
+            fn compare(&self, t: &'a [T]) -> CompareResult {
+                let tokens = self.as_slice();
+                for (i, expected) in t.iter().enumerate() {
+                    match tokens.get(i) {
+                        Some((token, _)) if token == expected => {}
+                        Some(_) => return CompareResult::Error,
+                        None => return CompareResult::Incomplete,
+                    }
+                }
+                CompareResult::Ok
+            }
+
+            fn compare_no_case(&self, t: &'a [T]) -> CompareResult {
+                self.compare(t)
+            }
+        }
+
+        impl<T: Clone> InputTakeAtPosition for $ty<'_, T> {
+            type Item = (T, Span);
+
+            fn split_at_position<P, E: ParseError<Self>>(
+                &self,
+                predicate: P,
+            ) -> IResult<Self, Self, E>
+            where
+                P: Fn(Self::Item) -> bool,
+            {
+                match self.position(predicate) {
+                    Some(n) => Ok(self.take_split(n)),
+                    None => Err(nom::Err::Incomplete(nom::Needed::new(1))),
+                }
+            }
+
+            fn split_at_position1<P, E: ParseError<Self>>(
+                &self,
+                predicate: P,
+                e: ErrorKind,
+            ) -> IResult<Self, Self, E>
+            where
+                P: Fn(Self::Item) -> bool,
+            {
+                match self.position(predicate) {
+                    Some(0) => Err(nom::Err::Error(E::from_error_kind(self.clone(), e))),
+                    Some(n) => Ok(self.take_split(n)),
+                    None => Err(nom::Err::Incomplete(nom::Needed::new(1))),
+                }
+            }
+
+            fn split_at_position_complete<P, E: ParseError<Self>>(
+                &self,
+                predicate: P,
+            ) -> IResult<Self, Self, E>
+            where
+                P: Fn(Self::Item) -> bool,
+            {
+                match self.position(predicate) {
+                    Some(n) => Ok(self.take_split(n)),
+                    None => Ok(self.take_split(self.len())),
+                }
+            }
+
+            fn split_at_position1_complete<P, E: ParseError<Self>>(
+                &self,
+                predicate: P,
+                e: ErrorKind,
+            ) -> IResult<Self, Self, E>
+            where
+                P: Fn(Self::Item) -> bool,
+            {
+                match self.position(predicate) {
+                    Some(0) => Err(nom::Err::Error(E::from_error_kind(self.clone(), e))),
+                    Some(n) => Ok(self.take_split(n)),
+                    None if self.is_empty() => {
+                        Err(nom::Err::Error(E::from_error_kind(self.clone(), e)))
+                    }
+                    None => Ok(self.take_split(self.len())),
+                }
+            }
+        }
+    };
+}
+
+impl<T> Clone for BufferedTokens<'_, T> {
+    fn clone(&self) -> Self {
+        BufferedTokens {
+            source: self.source,
+            tokens: self.tokens.clone(),
+            start: self.start,
+            end: self.end,
+            lex_error: self.lex_error.clone(),
+        }
+    }
+}
+
+impl<T> Clone for TokenSlice<'_, T> {
+    fn clone(&self) -> Self {
+        TokenSlice {
+            source: self.source,
+            tokens: self.tokens,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+impl_token_buffer!(BufferedTokens, Elements<T>, |this| Elements {
+    tokens: this.tokens.clone(),
+    range: this.start..this.end,
+});
+
+impl_token_buffer!(
+    TokenSlice,
+    iter::Cloned<slice::Iter<'i, (T, Span)>>,
+    |this| this.tokens[this.start..this.end].iter().cloned()
+);
+
+/// An iterator over the tokens of a [`BufferedTokens`], used by its [`InputIter`]
+/// implementation.
+pub struct Elements<T> {
+    tokens: Rc<[(T, Span)]>,
+    range: Range<usize>,
+}
+
+impl<T: Clone> Iterator for Elements<T> {
+    type Item = (T, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.range.next()?;
+        Some(self.tokens[i].clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
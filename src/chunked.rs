@@ -0,0 +1,572 @@
+//! An input type that lexes lazily and only keeps the tokens that are still reachable.
+
+use alloc::{
+    collections::{btree_map::Entry, BTreeMap, VecDeque},
+    rc::Rc,
+};
+use core::{
+    cell::RefCell,
+    fmt,
+    ops::{Range, RangeFrom, RangeFull, RangeTo},
+};
+
+use logos::{Logos, Source, Span};
+use nom::{
+    error::{ErrorKind, ParseError},
+    Compare, CompareResult, IResult, InputIter, InputLength, InputTake, InputTakeAtPosition,
+    Offset, Slice,
+};
+
+use crate::{SourceSlice, TokenSource, Tokens};
+
+/// The number of tokens lexed at once by [`ChunkedTokens::new`]
+const DEFAULT_CHUNK_SIZE: usize = 256;
+
+/// A nom input for very large sources, that lexes in chunks of tokens and keeps them in a
+/// rolling buffer.
+///
+/// Like [`Tokens`], it lexes lazily, but every token is lexed only once. All clones share one
+/// buffer, which drops the tokens that every live clone has already passed. How many tokens are
+/// kept in memory therefore depends on how far the parser backtracks, not on the size of the
+/// source.
+///
+/// Lengths and offsets used by the nom traits are measured in bytes, like the default
+/// [`LengthUnit`](crate::LengthUnit) of [`Tokens`], so measuring the input doesn't lex the rest
+/// of the source. For the same reason, [`nom::bytes::complete::tag`] can't be used to match
+/// token sequences. Hooks configured on a [`Tokens`] are applied when converting it with
+/// [`Tokens::chunked`].
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{ChunkedTokens, Tokens};
+///
+/// let source = "1 + ".repeat(10_000) + "1";
+/// let mut tokens = Tokens::<Token>::new(&source).chunked(100);
+///
+/// let mut sum = 0;
+/// while let (rest, Some((token, _, _))) = tokens.next_token() {
+///     if let Token::Number(n) = token {
+///         sum += n;
+///     }
+///     tokens = rest;
+///     assert!(tokens.buffered_len() <= 100);
+/// }
+/// assert_eq!(sum, 10_001);
+/// ```
+pub struct ChunkedTokens<'i, T>
+where
+    T: Logos<'i>,
+{
+    source: &'i T::Source,
+    window: Rc<RefCell<Window<'i, T>>>,
+    /// The index of the next token
+    index: usize,
+    /// The byte position after the previous token
+    pos: usize,
+    /// The byte position where the input ends
+    end: usize,
+}
+
+/// The tokens shared by all clones of a [`ChunkedTokens`]
+struct Window<'i, T: Logos<'i>> {
+    /// Lexes the tokens that weren't buffered yet
+    tokens: Tokens<'i, T>,
+    buffer: VecDeque<(T, Span)>,
+    /// The index of the first token in the buffer
+    first: usize,
+    chunk_size: usize,
+    /// The number of live inputs at each token index
+    cursors: BTreeMap<usize, usize>,
+}
+
+impl<'i, T: Logos<'i>> Window<'i, T> {
+    fn register(&mut self, index: usize) {
+        *self.cursors.entry(index).or_default() += 1;
+    }
+
+    /// Unregisters an input at `index` and drops the tokens that no input can reach anymore
+    fn release(&mut self, index: usize) {
+        if let Entry::Occupied(mut entry) = self.cursors.entry(index) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+        let keep = self.cursors.keys().next().copied().unwrap_or(usize::MAX);
+        while self.first < keep && self.buffer.pop_front().is_some() {
+            self.first += 1;
+        }
+    }
+}
+
+impl<'i, T> Window<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    /// Returns the token at `index`, lexing more chunks if necessary
+    fn get(&mut self, index: usize) -> Option<(T, Span)> {
+        while index >= self.first + self.buffer.len() {
+            let len = self.buffer.len();
+            for _ in 0..self.chunk_size {
+                match self.tokens.lex_next() {
+                    Some(token) => self.buffer.push_back(token),
+                    None => break,
+                }
+            }
+            if self.buffer.len() == len {
+                return None;
+            }
+        }
+        self.buffer.get(index - self.first).cloned()
+    }
+}
+
+impl<'i, T> ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Default + Clone,
+{
+    /// Creates an input that lexes `source` in chunks of 256 tokens.
+    pub fn new(source: &'i T::Source) -> Self {
+        Tokens::new(source).chunked(DEFAULT_CHUNK_SIZE)
+    }
+}
+
+impl<'i, T> ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    pub(crate) fn from_tokens(tokens: Tokens<'i, T>, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        let source = tokens.source();
+        let pos = tokens.as_lexer().span().end;
+        let mut window = Window {
+            tokens,
+            buffer: VecDeque::new(),
+            first: 0,
+            chunk_size,
+            cursors: BTreeMap::new(),
+        };
+        window.register(0);
+        ChunkedTokens {
+            source,
+            window: Rc::new(RefCell::new(window)),
+            index: 0,
+            pos,
+            end: source.len(),
+        }
+    }
+
+    /// Returns the source string, including the parts that were already consumed.
+    pub fn source(&self) -> &'i T::Source {
+        self.source
+    }
+
+    /// Returns the number of remaining bytes.
+    pub fn len(&self) -> usize {
+        self.end - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of tokens currently held in the shared buffer.
+    pub fn buffered_len(&self) -> usize {
+        self.window.borrow().buffer.len()
+    }
+
+    pub fn peek(&self) -> Option<(T, &'i SourceSlice<'i, T>)> {
+        let (t, span) = self.get(self.index)?;
+        Some((t, self.source_slice(span)))
+    }
+
+    /// Returns the next token together with its span and source slice, without consuming it.
+    pub fn peek_with_span(&self) -> Option<(T, Span, &'i SourceSlice<'i, T>)> {
+        let (t, span) = self.get(self.index)?;
+        Some((t, span.clone(), self.source_slice(span)))
+    }
+
+    /// Returns the lexing error at the current position, if any. Like [`Tokens`], the input
+    /// ends before source text that the lexer can't recognize; see [`Tokens::lex_error`].
+    pub fn lex_error(&self) -> Option<(T::Error, Span)> {
+        let mut window = self.window.borrow_mut();
+        if window.get(self.index).is_some() {
+            return None;
+        }
+        let (e, span) = window.tokens.lex_error()?;
+        (span.end <= self.end).then_some((e, span))
+    }
+
+    /// Returns the number of bytes up to the end of the tokens, which is less than `len()` if
+    /// the tokens stop at a lexing error. All tokens of the input must already be buffered.
+    fn tokens_len(&self) -> usize {
+        match self.window.borrow().tokens.lex_error() {
+            Some((_, span)) if span.end <= self.end => span.start - self.pos,
+            _ => self.len(),
+        }
+    }
+
+    pub fn advance(self) -> Self {
+        self.next_token().0
+    }
+
+    /// Consumes the next token and returns it together with its span and source slice.
+    #[allow(clippy::type_complexity)]
+    pub fn next_token(mut self) -> (Self, Option<(T, Span, &'i SourceSlice<'i, T>)>) {
+        let token = self
+            .next_spanned()
+            .map(|(t, span)| (t, span.clone(), self.source_slice(span)));
+        (self, token)
+    }
+
+    fn source_slice(&self, span: Span) -> &'i SourceSlice<'i, T> {
+        self.source.slice(span).expect("span is within the source")
+    }
+
+    /// Returns the token at `index`, if it ends before the end of the input
+    fn get(&self, index: usize) -> Option<(T, Span)> {
+        let token = self.window.borrow_mut().get(index)?;
+        (token.1.end <= self.end).then_some(token)
+    }
+
+    fn next_spanned(&mut self) -> Option<(T, Span)> {
+        let (t, span) = self.get(self.index)?;
+        self.seek(self.index + 1, span.end);
+        Some((t, span))
+    }
+
+    fn seek(&mut self, index: usize, pos: usize) {
+        let mut window = self.window.borrow_mut();
+        window.register(index);
+        window.release(self.index);
+        drop(window);
+        self.index = index;
+        self.pos = pos;
+    }
+
+    /// Returns an input at `index` and `pos` that ends at `end`
+    fn at(&self, index: usize, pos: usize, end: usize) -> Self {
+        self.window.borrow_mut().register(index);
+        ChunkedTokens {
+            source: self.source,
+            window: self.window.clone(),
+            index,
+            pos,
+            end,
+        }
+    }
+
+    /// Returns the input after the first `count` bytes
+    fn skip_bytes(&self, count: usize) -> Self {
+        let pos = self.pos + count;
+        let mut index = self.index;
+        while self.get(index).is_some_and(|(_, span)| span.end <= pos) {
+            index += 1;
+        }
+        self.at(index, pos, self.end)
+    }
+}
+
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    /// Returns a [`ChunkedTokens`] that lexes the remaining input `chunk_size` tokens at a
+    /// time, and only keeps the tokens that are still reachable by one of its clones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn chunked(self, chunk_size: usize) -> ChunkedTokens<'i, T> {
+        ChunkedTokens::from_tokens(self, chunk_size)
+    }
+}
+
+impl<'i, T: Logos<'i>> Clone for ChunkedTokens<'i, T> {
+    fn clone(&self) -> Self {
+        self.window.borrow_mut().register(self.index);
+        ChunkedTokens {
+            source: self.source,
+            window: self.window.clone(),
+            index: self.index,
+            pos: self.pos,
+            end: self.end,
+        }
+    }
+}
+
+impl<'i, T: Logos<'i>> Drop for ChunkedTokens<'i, T> {
+    fn drop(&mut self) {
+        self.window.borrow_mut().release(self.index);
+    }
+}
+
+impl<'i, T> fmt::Debug for ChunkedTokens<'i, T>
+where
+    T: Logos<'i>,
+    SourceSlice<'i, T>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rest = self.source.slice(self.pos..self.end);
+        f.debug_tuple("ChunkedTokens")
+            .field(&rest.expect("span is within the source"))
+            .finish()
+    }
+}
+
+/// An iterator over the tokens of a [`ChunkedTokens`], used by its [`InputIter`]
+/// implementation.
+pub struct ChunkedIter<'i, T>
+where
+    T: Logos<'i>,
+{
+    tokens: ChunkedTokens<'i, T>,
+    /// The byte position of the input the iterator was created from
+    start: usize,
+}
+
+impl<'i, T> Iterator for ChunkedIter<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    type Item = (usize, (T, Span));
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.tokens.pos - self.start;
+        self.tokens.next_spanned().map(|token| (offset, token))
+    }
+}
+
+impl<'i, T> InputIter for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    type Item = (T, Span);
+
+    type Iter = ChunkedIter<'i, T>;
+
+    type IterElem = core::iter::Map<ChunkedIter<'i, T>, fn((usize, (T, Span))) -> (T, Span)>;
+
+    fn iter_indices(&self) -> Self::Iter {
+        ChunkedIter {
+            tokens: self.clone(),
+            start: self.pos,
+        }
+    }
+
+    fn iter_elements(&self) -> Self::IterElem {
+        self.iter_indices().map(|(_, token)| token)
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.iter_indices()
+            .find(|(_, t)| predicate(t.clone()))
+            .map(|(offset, _)| offset)
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, nom::Needed> {
+        if count == 0 {
+            return Ok(0);
+        }
+        match self.get(self.index + count - 1) {
+            Some((_, span)) => Ok(span.end - self.pos),
+            None => Err(nom::Needed::Unknown),
+        }
+    }
+}
+
+impl<'i, T> InputLength for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn input_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<'i, T> InputTake for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn take(&self, count: usize) -> Self {
+        self.at(self.index, self.pos, self.pos + count)
+    }
+
+    fn take_split(&self, count: usize) -> (Self, Self) {
+        (self.skip_bytes(count), self.take(count))
+    }
+}
+
+impl<'i, T> Offset for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn offset(&self, second: &Self) -> usize {
+        second.pos - self.pos
+    }
+}
+
+impl<'i, T> Slice<Range<usize>> for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, range: Range<usize>) -> Self {
+        let mut tokens = self.skip_bytes(range.start);
+        tokens.end = self.pos + range.end;
+        tokens
+    }
+}
+
+impl<'i, T> Slice<RangeTo<usize>> for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, range: RangeTo<usize>) -> Self {
+        self.take(range.end)
+    }
+}
+
+impl<'i, T> Slice<RangeFrom<usize>> for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, range: RangeFrom<usize>) -> Self {
+        self.skip_bytes(range.start)
+    }
+}
+
+impl<'i, T> Slice<RangeFull> for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, _: RangeFull) -> Self {
+        self.clone()
+    }
+}
+
+impl<'a, 'i, T> Compare<&'a [T]> for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn compare(&self, t: &'a [T]) -> CompareResult {
+        for (i, expected) in t.iter().enumerate() {
+            match self.get(self.index + i) {
+                Some((token, _)) if token == *expected => {}
+                Some(_) => return CompareResult::Error,
+                None => return CompareResult::Incomplete,
+            }
+        }
+        CompareResult::Ok
+    }
+
+    fn compare_no_case(&self, t: &'a [T]) -> CompareResult {
+        self.compare(t)
+    }
+}
+
+impl<'i, T> InputTakeAtPosition for ChunkedTokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    type Item = (T, Span);
+
+    fn split_at_position<P, E: ParseError<Self>>(&self, predicate: P) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match self.position(predicate) {
+            Some(n) => Ok(self.take_split(n)),
+            None => Err(nom::Err::Incomplete(nom::Needed::new(1))),
+        }
+    }
+
+    fn split_at_position1<P, E: ParseError<Self>>(
+        &self,
+        predicate: P,
+        e: ErrorKind,
+    ) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match self.position(predicate) {
+            Some(0) => Err(nom::Err::Error(E::from_error_kind(self.clone(), e))),
+            Some(n) => Ok(self.take_split(n)),
+            None => Err(nom::Err::Incomplete(nom::Needed::new(1))),
+        }
+    }
+
+    fn split_at_position_complete<P, E: ParseError<Self>>(
+        &self,
+        predicate: P,
+    ) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match self.position(predicate) {
+            Some(n) => Ok(self.take_split(n)),
+            None => Ok(self.take_split(self.tokens_len())),
+        }
+    }
+
+    fn split_at_position1_complete<P, E: ParseError<Self>>(
+        &self,
+        predicate: P,
+        e: ErrorKind,
+    ) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match self.position(predicate) {
+            Some(0) => Err(nom::Err::Error(E::from_error_kind(self.clone(), e))),
+            Some(n) => Ok(self.take_split(n)),
+            None if self.peek().is_none() => {
+                Err(nom::Err::Error(E::from_error_kind(self.clone(), e)))
+            }
+            None => Ok(self.take_split(self.tokens_len())),
+        }
+    }
+}
@@ -0,0 +1,1644 @@
+//! Token-aware nom combinators that work with any token type.
+
+#![allow(clippy::type_complexity)]
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{cell::RefCell, fmt, marker::PhantomData, mem};
+
+use logos::{Logos, Span};
+use nom::{
+    error::{ErrorKind, FromExternalError, ParseError},
+    multi::separated_list0,
+    IResult, Parser,
+};
+
+use crate::{span::Spanned, LexErrorPolicy, LexErrors, SourceSlice, TokenSource, Tokens};
+
+/// Applies `parser` repeatedly until the input is exhausted, collecting the results.
+///
+/// Unlike [`nom::multi::many0`], this fails if `parser` stops before the end of the input. The
+/// error is created with [`ErrorKind::Eof`] and points at the first token that couldn't be
+/// consumed, so its span can be used for diagnostics. It also fails if the tokens end early at
+/// a [lexing error](Tokens::lex_error).
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::repeat_to_eof, Tokens};
+/// use nom::InputIter;
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let (_, numbers) = repeat_to_eof(parse_number)(Tokens::new("1 2 3")).unwrap();
+/// assert_eq!(numbers, [1, 2, 3]);
+///
+/// let (_, numbers) = repeat_to_eof(parse_number)(Tokens::new("")).unwrap();
+/// assert!(numbers.is_empty());
+///
+/// match repeat_to_eof(parse_number)(Tokens::new("1 2 + 3")) {
+///     Err(nom::Err::Error(e)) => {
+///         let (token, span) = e.input.iter_elements().next().unwrap();
+///         assert_eq!(token, Token::Plus);
+///         assert_eq!(span, 4..5);
+///     }
+///     _ => panic!("expected an error"),
+/// }
+///
+/// match repeat_to_eof(parse_number)(Tokens::new("1 $ 2")) {
+///     Err(nom::Err::Error(e)) => assert_eq!(e.input.lex_error(), Some(((), 2..3))),
+///     _ => panic!("expected an error"),
+/// }
+/// ```
+///
+/// Consuming a synthetic token, like the ones inserted by [`Tokens::with_layout`], counts as
+/// progress, although it is empty in the source:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// #
+/// #     Newline,
+/// #     Indent,
+/// #     Dedent,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::repeat_to_eof, Tokens};
+/// use nom::{branch::alt, combinator::value, IResult, Parser};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let tokens = Tokens::new("1\n2").with_layout(Token::Newline, Token::Indent, Token::Dedent);
+/// let result: IResult<_, _> =
+///     repeat_to_eof(alt((parse_number.map(Some), value(None, Token::Newline))))(tokens);
+///
+/// assert_eq!(result.unwrap().1, [Some(1), None, Some(2), None]);
+/// ```
+pub fn repeat_to_eof<'i, T, O, E, P>(
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, Vec<O>, E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |mut input| {
+        let mut items = Vec::new();
+        while input.peek().is_some() {
+            match parser.parse(input.clone()) {
+                Ok((rest, item)) => {
+                    // infinite loop check: the parser must always consume. Synthetic tokens
+                    // don't advance in the source, so the number of consumed tokens is compared
+                    if rest == input {
+                        return Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Many0)));
+                    }
+                    items.push(item);
+                    input = rest;
+                }
+                Err(nom::Err::Error(_)) => {
+                    return Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Eof)));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if input.lex_error().is_some() {
+            let fatal = input.lex_error_policy() == LexErrorPolicy::Fail;
+            let e = E::from_error_kind(input, ErrorKind::Eof);
+            return Err(if fatal {
+                nom::Err::Failure(e)
+            } else {
+                nom::Err::Error(e)
+            });
+        }
+        Ok((input, items))
+    }
+}
+
+/// Parses a list of `item`s separated by `sep` and enclosed in `open` and `close`, returning
+/// each item together with its span as a [`Spanned`].
+///
+/// The span of an item covers all tokens consumed by `item`, excluding surrounding trivia.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("[")]
+/// #     LBracket,
+/// #
+/// #     #[token("]")]
+/// #     RBracket,
+/// #
+/// #     #[token(",")]
+/// #     Comma,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::delimited_list_spanned, span::Spanned, Tokens};
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let mut parser =
+///     delimited_list_spanned(Token::LBracket, parse_number, Token::Comma, Token::RBracket);
+/// let (_, items) = parser(Tokens::new("[ 1, 2, 3 ]")).unwrap();
+///
+/// assert_eq!(
+///     items,
+///     [Spanned::new(1, 2..3), Spanned::new(2, 5..6), Spanned::new(3, 8..9)],
+/// );
+/// ```
+pub fn delimited_list_spanned<'i, T, O, E, P>(
+    open: T,
+    mut item: P,
+    sep: T,
+    close: T,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, Vec<Spanned<O>>, E>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| {
+        let (input, _) = token(open.clone())(input)?;
+        let (input, items) =
+            separated_list0(token(sep.clone()), spanned(|i| item.parse(i)))(input)?;
+        let (input, _) = token(close.clone())(input)?;
+        Ok((input, items))
+    }
+}
+
+/// Runs `parser` and returns its output together with the span of the tokens it consumed, as a
+/// [`Spanned`].
+///
+/// The span starts at the first consumed token and ends where the last consumed token ends, so
+/// skipped whitespace around it is not included. If nothing was consumed, the span is empty and
+/// positioned at the current offset.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::spanned, Tokens};
+/// use nom::{sequence::separated_pair, IResult};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let mut sum = spanned(separated_pair(parse_number, Token::Plus, parse_number));
+///
+/// let result: IResult<_, _> = sum(Tokens::new("  1 + 2  "));
+/// let (_, sum) = result.unwrap();
+///
+/// assert_eq!(sum.value, (1, 2));
+/// assert_eq!(sum.span, 2..7);
+/// ```
+pub fn spanned<'i, T, O, E, P>(
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, Spanned<O>, E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+{
+    move |input| {
+        let (rest, o) = parser.parse(input.clone())?;
+        let span = consumed_span(&input, &rest);
+        Ok((rest, Spanned::new(o, span)))
+    }
+}
+
+/// Runs `parser` and returns its output together with the 1-based `(line, column)` positions
+/// where the consumed tokens start and end.
+///
+/// This works like [`spanned`], but converts the span with the input's
+/// [line index](Tokens::line_index).
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::located, Tokens};
+/// use nom::{sequence::separated_pair, IResult};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let mut sum = located(separated_pair(parse_number, Token::Plus, parse_number));
+///
+/// let result: IResult<_, _> = sum(Tokens::new("\n  1 +\n 22"));
+/// let (_, (_, start, end)) = result.unwrap();
+///
+/// assert_eq!(start, (2, 3));
+/// assert_eq!(end, (3, 4));
+/// ```
+pub fn located<'i, T, O, E, P>(
+    parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, (O, (usize, usize), (usize, usize)), E>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+{
+    let mut parser = spanned(parser);
+    move |input| {
+        let start = input.clone();
+        let (rest, Spanned { value: o, span }) = parser(input)?;
+        let index = start.line_index();
+        Ok((
+            rest,
+            (o, index.line_col(span.start), index.line_col(span.end)),
+        ))
+    }
+}
+
+/// Runs `parser` and returns the source text it consumed, instead of its output.
+///
+/// This is the token-aware counterpart of [`nom::combinator::recognize`]. The slice spans from
+/// the first to the last consumed token, including any whitespace between them. For a byte
+/// source, it is a `[u8]` slice.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::recognize_str, Tokens};
+/// use nom::{sequence::separated_pair, IResult};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let mut sum = recognize_str(separated_pair(parse_number, Token::Plus, parse_number));
+///
+/// let result: IResult<_, _> = sum(Tokens::new(" 1 +   2 + 3"));
+/// let (rest, text) = result.unwrap();
+///
+/// assert_eq!(text, "1 +   2");
+/// assert_eq!(rest.remainder(), " + 3");
+/// ```
+pub fn recognize_str<'i, T, O, E, P>(
+    parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, &'i SourceSlice<'i, T>, E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+{
+    let mut parser = spanned(parser);
+    move |input| {
+        let (rest, Spanned { span, .. }) = parser(input)?;
+        let slice = rest.source_slice(span);
+        Ok((rest, slice))
+    }
+}
+
+/// Returns the span of the tokens consumed between `before` and `after`.
+pub(crate) fn consumed_span<'i, T>(before: &Tokens<'i, T>, after: &Tokens<'i, T>) -> Span
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    let end = after.lexer.span().end;
+    match before.peek_spanned() {
+        Some((_, span)) if span.start < end => span.start..end,
+        _ => end..end,
+    }
+}
+
+/// Applies `parser`, recovering if it fails.
+///
+/// If `parser` succeeds, its output is returned as `(Some(output), None)`. If it fails with
+/// [`nom::Err::Error`], tokens are skipped until the next token contained in `sync`, which is
+/// not consumed, and `(None, Some(error))` is returned, so the surrounding parser can continue
+/// after the erroneous part. When `parser` fails at a token in `sync`, nothing is skipped, so
+/// this succeeds without consuming input.
+///
+/// [`nom::Err::Failure`], e.g. from [`nom::combinator::cut`], and [`nom::Err::Incomplete`] are
+/// returned unchanged.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[token(";")]
+/// #     Semicolon,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::recover_until, Tokens};
+/// use nom::{multi::many0, sequence::terminated, IResult};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let statement = terminated(recover_until(&[Token::Semicolon], number), Token::Semicolon);
+///
+/// let result: IResult<_, _> = many0(statement)(Tokens::new("1; + + 2; 3;"));
+/// let (rest, statements) = result.unwrap();
+///
+/// assert!(rest.is_empty());
+/// let numbers: Vec<_> = statements.iter().map(|(n, _)| *n).collect();
+/// assert_eq!(numbers, [Some(1), None, Some(3)]);
+/// assert_eq!(statements[1].1.as_ref().unwrap().input.peek(), Some((Token::Plus, "+")));
+///
+/// // failures aren't recovered from
+/// use nom::combinator::cut;
+///
+/// let result: IResult<_, _> = recover_until(&[Token::Semicolon], cut(number))(Tokens::new("+ 1;"));
+/// assert!(matches!(result, Err(nom::Err::Failure(_))));
+/// ```
+pub fn recover_until<'a, 'i, T, O, E, P>(
+    sync: &'a [T],
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, (Option<O>, Option<E>), E> + 'a
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E> + 'a,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| match parser.parse(input.clone()) {
+        Ok((rest, output)) => Ok((rest, (Some(output), None))),
+        Err(nom::Err::Error(e)) => {
+            let (rest, _) = input.skip_while(|token| !sync.contains(token));
+            Ok((rest, (None, Some(e))))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the error of a token-level parser that didn't match the next token of `input`, like
+/// the parsers implemented by [`token_parser`](crate::token_parser). See [`end_of_tokens`].
+fn token_error<'i, T, E>(input: Tokens<'i, T>, kind: ErrorKind) -> nom::Err<E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    match end_of_tokens(&input, kind) {
+        Some(e) => e,
+        None => nom::Err::Error(E::from_error_kind(input, kind)),
+    }
+}
+
+/// Returns the error of a token-level parser that ran out of tokens at `at`, if it must be
+/// reported as something other than [`nom::Err::Error`]: at the end of a
+/// [streaming](Tokens::streaming) chunk, more tokens may follow, so this is
+/// [`nom::Err::Incomplete`]; at a lexing error with [`LexErrorPolicy::Fail`], this is a
+/// [`nom::Err::Failure`] with `kind` at the error.
+fn end_of_tokens<'i, T, E>(at: &Tokens<'i, T>, kind: ErrorKind) -> Option<nom::Err<E>>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    if at.peek_spanned().is_some() {
+        return None;
+    }
+    if at.is_streaming() {
+        return Some(nom::Err::Incomplete(nom::Needed::Unknown));
+    }
+    if at.is_at_fatal_lex_error() {
+        return Some(nom::Err::Failure(E::from_error_kind(at.clone(), kind)));
+    }
+    None
+}
+
+/// Consumes one token for which `predicate` returns `true`, and returns it together with its
+/// span and source slice.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::satisfy, Tokens};
+/// use nom::IResult;
+///
+/// let mut positive = satisfy(|t| matches!(t, Token::Number(n) if *n > 0));
+///
+/// let result: IResult<_, _> = positive(Tokens::new("42 +"));
+/// assert_eq!(result.unwrap().1, (Token::Number(42), 0..2, "42"));
+///
+/// let result: IResult<_, _> = positive(Tokens::new("-1"));
+/// assert!(result.is_err());
+/// ```
+pub fn satisfy<'i, T, E, F>(
+    predicate: F,
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, (T, Span, &'i SourceSlice<'i, T>), E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+    F: Fn(&T) -> bool,
+{
+    move |input| match input.peek_with_span() {
+        Some(token) if predicate(&token.0) => Ok((input.advance(), token)),
+        _ => Err(token_error(input, ErrorKind::Satisfy)),
+    }
+}
+
+/// Consumes any single token and returns it together with its span and source slice. Fails
+/// only at the end of the input.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::any_token, Tokens};
+/// use nom::IResult;
+///
+/// let result: IResult<_, _> = any_token(Tokens::new(" + 1"));
+/// let (rest, token) = result.unwrap();
+/// assert_eq!(token, (Token::Plus, 1..2, "+"));
+///
+/// let result: IResult<_, _> = any_token(rest.advance());
+/// assert!(result.is_err());
+/// ```
+pub fn any_token<'i, T, E>(
+    input: Tokens<'i, T>,
+) -> IResult<Tokens<'i, T>, (T, Span, &'i SourceSlice<'i, T>), E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    match input.clone().next_token() {
+        (rest, Some(token)) => Ok((rest, token)),
+        (_, None) => Err(token_error(input, ErrorKind::Eof)),
+    }
+}
+
+/// Matches a single token that is equal to `expected`, and returns its source slice.
+///
+/// This matches and fails like the parser implemented by [`token_parser`](crate::token_parser)
+/// with the default options: a mismatch is an error with [`ErrorKind::IsA`], the end of a
+/// [streaming](Tokens::streaming) chunk is [`nom::Err::Incomplete`], and a lexing error with
+/// [`LexErrorPolicy::Fail`] is a [`nom::Err::Failure`]. Unlike the macro, it also works for
+/// token types defined in other crates.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::token, Tokens};
+/// use nom::{sequence::pair, IResult};
+///
+/// let result: IResult<_, _> = pair(token(Token::Number(1)), token(Token::Plus))(Tokens::new("1 +"));
+/// assert_eq!(result.unwrap().1, ("1", "+"));
+///
+/// let result: IResult<_, _> = token(Token::Plus)(Tokens::new("1 +"));
+/// assert!(result.is_err());
+///
+/// // the next token may still arrive
+/// let result: IResult<_, _> = token(Token::Number(2))(Tokens::new("1 +").streaming().advance());
+/// assert!(matches!(result, Err(nom::Err::Incomplete(_))));
+///
+/// // lexing errors are fatal with `LexErrorPolicy::Fail`
+/// use logos_nom_bridge::LexErrorPolicy;
+///
+/// let tokens = Tokens::new("1 $").on_lex_error(LexErrorPolicy::Fail).advance();
+/// let result: IResult<_, _> = token(Token::Plus)(tokens);
+/// assert!(matches!(result, Err(nom::Err::Failure(_))));
+/// ```
+pub fn token<'i, T, E>(
+    expected: T,
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, &'i SourceSlice<'i, T>, E>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| match input.peek() {
+        Some((t, s)) if t == expected => Ok((input.advance(), s)),
+        _ => Err(token_error(input, ErrorKind::IsA)),
+    }
+}
+
+/// Matches a single token that is equal to `expected`, and returns its span.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("fn")]
+/// #     Fn,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Ident,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::token_span, Tokens};
+/// use nom::IResult;
+///
+/// let result: IResult<_, _> = token_span(Token::Fn)(Tokens::new("  fn main"));
+/// assert_eq!(result.unwrap().1, 2..4);
+/// ```
+pub fn token_span<'i, T, E>(
+    expected: T,
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, Span, E>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| match input.peek_spanned() {
+        Some((t, span)) if t == expected => Ok((input.advance(), span)),
+        _ => Err(token_error(input, ErrorKind::IsA)),
+    }
+}
+
+/// Matches a fixed sequence of tokens, and returns the source slice and span they cover.
+///
+/// Unlike [`nom::bytes::complete::tag`], this works regardless of the input's
+/// [`LengthUnit`](crate::LengthUnit).
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("else")]
+/// #     Else,
+/// #
+/// #     #[token("if")]
+/// #     If,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::tag_tokens, Tokens};
+/// use nom::IResult;
+///
+/// let mut else_if = tag_tokens(&[Token::Else, Token::If]);
+///
+/// let result: IResult<_, _> = else_if(Tokens::new("else  if"));
+/// assert_eq!(result.unwrap().1, ("else  if", 0..8));
+///
+/// let result: IResult<_, _> = else_if(Tokens::new("else else"));
+/// assert!(result.is_err());
+/// ```
+pub fn tag_tokens<'a, 'i, T, E>(
+    tokens: &'a [T],
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, (&'i SourceSlice<'i, T>, Span), E> + 'a
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| {
+        let mut rest = input.clone();
+        for expected in tokens {
+            match rest.peek_spanned() {
+                Some((t, _)) if t == *expected => rest = rest.advance(),
+                // the error is reported at the start of the tag
+                _ => {
+                    return Err(end_of_tokens(&rest, ErrorKind::Tag).unwrap_or_else(|| {
+                        nom::Err::Error(E::from_error_kind(input, ErrorKind::Tag))
+                    }))
+                }
+            }
+        }
+        let span = consumed_span(&input, &rest);
+        Ok((rest, (input.source_slice(span.clone()), span)))
+    }
+}
+
+/// Matches a single token that is contained in `set`, and returns it together with its source
+/// slice.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[token("-")]
+/// #     Minus,
+/// #
+/// #     #[token("*")]
+/// #     Star,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::one_of_tokens, Tokens};
+/// use nom::IResult;
+///
+/// let mut additive = one_of_tokens(&[Token::Plus, Token::Minus]);
+///
+/// let result: IResult<_, _> = additive(Tokens::new("-"));
+/// assert_eq!(result.unwrap().1, (Token::Minus, "-"));
+///
+/// let result: IResult<_, _> = additive(Tokens::new("*"));
+/// assert!(result.is_err());
+/// ```
+pub fn one_of_tokens<'a, 'i, T, E>(
+    set: &'a [T],
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, (T, &'i SourceSlice<'i, T>), E> + 'a
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| match input.peek() {
+        Some((t, s)) if set.contains(&t) => Ok((input.advance(), (t, s))),
+        _ => Err(token_error(input, ErrorKind::OneOf)),
+    }
+}
+
+/// Matches a single token that is not equal to `unexpected`, and returns it together with its
+/// source slice. Fails at the end of the input.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("{")]
+/// #     LBrace,
+/// #
+/// #     #[token("}")]
+/// #     RBrace,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Word,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::not_token, Tokens};
+/// use nom::{multi::many0, IResult};
+///
+/// let result: IResult<_, _> = many0(not_token(Token::RBrace))(Tokens::new("a { b } c"));
+/// let (rest, body) = result.unwrap();
+///
+/// assert_eq!(body.len(), 3);
+/// assert_eq!(rest.peek(), Some((Token::RBrace, "}")));
+/// ```
+pub fn not_token<'i, T, E>(
+    unexpected: T,
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, (T, &'i SourceSlice<'i, T>), E>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| match input.peek() {
+        Some((t, s)) if t != unexpected => Ok((input.advance(), (t, s))),
+        _ => Err(token_error(input, ErrorKind::NoneOf)),
+    }
+}
+
+/// Matches a single token that is not contained in `set`, and returns it together with its
+/// source slice. Fails at the end of the input.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token(";")]
+/// #     Semicolon,
+/// #
+/// #     #[token("}")]
+/// #     RBrace,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Word,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::none_of_tokens, Tokens};
+/// use nom::{multi::many0, IResult};
+///
+/// let mut raw = many0(none_of_tokens(&[Token::Semicolon, Token::RBrace]));
+///
+/// let result: IResult<_, _> = raw(Tokens::new("a b c; d"));
+/// let (rest, words) = result.unwrap();
+///
+/// assert_eq!(words.len(), 3);
+/// assert_eq!(rest.peek(), Some((Token::Semicolon, ";")));
+/// ```
+pub fn none_of_tokens<'a, 'i, T, E>(
+    set: &'a [T],
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, (T, &'i SourceSlice<'i, T>), E> + 'a
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| match input.peek() {
+        Some((t, s)) if !set.contains(&t) => Ok((input.advance(), (t, s))),
+        _ => Err(token_error(input, ErrorKind::NoneOf)),
+    }
+}
+
+/// Matches a single token of the same enum variant as `kind` whose source slice is `text`, and
+/// returns the slice. Data contained in the token is ignored.
+///
+/// This is useful for contextual keywords that are lexed as identifiers.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token(":")]
+/// #     Colon,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Ident,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::keyword, Tokens};
+/// use nom::IResult;
+///
+/// let mut where_kw = keyword(Token::Ident, "where");
+///
+/// let result: IResult<_, _> = where_kw(Tokens::new("where t: copy"));
+/// assert_eq!(result.unwrap().1, "where");
+///
+/// let result: IResult<_, _> = where_kw(Tokens::new("when"));
+/// assert!(result.is_err());
+/// ```
+pub fn keyword<'a, 'i, T, E>(
+    kind: T,
+    text: &'a SourceSlice<'i, T>,
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, &'i SourceSlice<'i, T>, E> + 'a
+where
+    T: Logos<'i> + Clone + 'a,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    let kind = mem::discriminant(&kind);
+    move |input| match input.peek() {
+        Some((t, s)) if mem::discriminant(&t) == kind && s == text => Ok((input.advance(), s)),
+        _ => Err(token_error(input, ErrorKind::Tag)),
+    }
+}
+
+/// Succeeds only at the end of the input.
+///
+/// Unlike [`nom::combinator::eof`], the error doesn't wrap the whole remaining stream; it is
+/// created with [`FromExternalError`] from an [`ExpectedEnd`] that describes the first token that
+/// wasn't consumed. The input stored in the error starts at that token.
+///
+/// On [streaming](Tokens::streaming) input, reaching the end of the chunk returns
+/// [`nom::Err::Incomplete`]. When the tokens stop at a [lexing error](Tokens::lex_error), an
+/// error with [`ErrorKind::Eof`] is returned. Errors at the end that are skipped with
+/// [`LexErrorPolicy::Collect`] are recorded in the returned input.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::{end, ExpectedEnd}, Tokens};
+/// use nom::error::{ErrorKind, FromExternalError, ParseError};
+///
+/// #[derive(Debug)]
+/// struct Message(String);
+///
+/// impl<I> ParseError<I> for Message {
+///     fn from_error_kind(_: I, kind: ErrorKind) -> Self {
+///         Message(format!("{kind:?}"))
+///     }
+///
+///     fn append(_: I, _: ErrorKind, other: Self) -> Self {
+///         other
+///     }
+/// }
+///
+/// impl<'i, I> FromExternalError<I, ExpectedEnd<'i, Token>> for Message {
+///     fn from_external_error(_: I, _: ErrorKind, e: ExpectedEnd<'i, Token>) -> Self {
+///         Message(e.to_string())
+///     }
+/// }
+///
+/// assert!(end::<_, Message>(Tokens::new("  ")).is_ok());
+///
+/// match end::<_, Message>(Tokens::new("1 + 2").advance()) {
+///     Err(nom::Err::Error(Message(msg))) => {
+///         assert_eq!(msg, "expected end of input, found `+` at 2..3");
+///     }
+///     _ => panic!("expected an error"),
+/// }
+/// ```
+pub fn end<'i, T, E>(input: Tokens<'i, T>) -> IResult<Tokens<'i, T>, (), E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>
+        + FromExternalError<Tokens<'i, T>, ExpectedEnd<'i, T, SourceSlice<'i, T>>>,
+{
+    match input.peek_spanned() {
+        None if input.is_streaming() => Err(nom::Err::Incomplete(nom::Needed::Unknown)),
+        None if input.is_at_fatal_lex_error() => {
+            Err(nom::Err::Failure(E::from_error_kind(input, ErrorKind::Eof)))
+        }
+        None if input.lex_error().is_some() => {
+            Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Eof)))
+        }
+        // consume the lexing errors that are skipped at the end
+        None => Ok((input.next_token().0, ())),
+        Some((token, span)) => {
+            let slice = input.source_slice(span.clone());
+            let e = ExpectedEnd { token, span, slice };
+            Err(nom::Err::Error(E::from_external_error(
+                input,
+                ErrorKind::Eof,
+                e,
+            )))
+        }
+    }
+}
+
+/// The error produced by [`end`] when the input isn't exhausted. `S` is the type of source
+/// slices, which is `[u8]` for byte sources.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExpectedEnd<'i, T, S: ?Sized = str> {
+    /// The first token that wasn't consumed.
+    pub token: T,
+    /// The span of that token.
+    pub span: Span,
+    /// The source slice of that token.
+    pub slice: &'i S,
+}
+
+// not derived, because the derive would require `S: Clone`
+impl<T: Clone, S: ?Sized> Clone for ExpectedEnd<'_, T, S> {
+    fn clone(&self) -> Self {
+        ExpectedEnd {
+            token: self.token.clone(),
+            span: self.span.clone(),
+            slice: self.slice,
+        }
+    }
+}
+
+impl<T, S: fmt::Display + ?Sized> fmt::Display for ExpectedEnd<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected end of input, found `{}` at {}..{}",
+            self.slice, self.span.start, self.span.end
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug, S: fmt::Debug + fmt::Display + ?Sized> std::error::Error
+    for ExpectedEnd<'_, T, S>
+{
+}
+
+/// Matches a token `open`, followed by tokens up to the matching token `close`, and returns
+/// the tokens in between as a separate stream.
+///
+/// Nested `open`/`close` pairs are tracked, so the inner stream contains balanced pairs only.
+/// The returned stream starts after `open` and ends before `close`; its
+/// [`source`](Tokens::source) is cut off at the end, but spans remain relative to the whole
+/// source. Fails if the input doesn't start with `open` or if `close` is missing.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("{")]
+/// #     LBrace,
+/// #
+/// #     #[token("}")]
+/// #     RBrace,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Word,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::balanced, Tokens};
+/// use nom::IResult;
+///
+/// let mut block = balanced(Token::LBrace, Token::RBrace);
+///
+/// let result: IResult<_, _> = block(Tokens::new("{ a { b } c } d"));
+/// let (rest, body) = result.unwrap();
+///
+/// assert_eq!(body.remainder(), " a { b } c ");
+/// assert_eq!(body.token_count(), 5);
+/// assert_eq!(rest.peek(), Some((Token::Word, "d")));
+///
+/// let result: IResult<_, _> = block(Tokens::new("{ a { b }"));
+/// assert!(result.is_err());
+/// ```
+pub fn balanced<'i, T, E>(
+    open: T,
+    close: T,
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, Tokens<'i, T>, E>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| {
+        let (mut rest, start) = match input.clone().next_token() {
+            (rest, Some((t, span, _))) if t == open => (rest, span.end),
+            _ => return Err(token_error(input, ErrorKind::Tag)),
+        };
+        let mut depth = 1usize;
+        loop {
+            let (next, token) = rest.next_token();
+            rest = next;
+            match token {
+                Some((t, span, _)) if t == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let mut inner = sub_tokens(&input, start, span.start);
+                        inner.consumed += 1;
+                        return Ok((rest, inner));
+                    }
+                }
+                Some((t, _, _)) if t == open => depth += 1,
+                Some(_) => {}
+                None => {
+                    return Err(end_of_tokens(&rest, ErrorKind::Eof).unwrap_or_else(|| {
+                        nom::Err::Error(E::from_error_kind(input, ErrorKind::Eof))
+                    }))
+                }
+            }
+        }
+    }
+}
+
+/// Consumes tokens up to, but not including, the first token `until`, and returns them as a
+/// separate stream.
+///
+/// Like [`balanced`], the returned stream keeps spans relative to the whole source. Fails with
+/// [`ErrorKind::TakeUntil`] if `until` doesn't occur in the input.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token(";")]
+/// #     Semicolon,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Word,
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::take_until_token, Tokens};
+/// use nom::IResult;
+///
+/// let result: IResult<_, _> = take_until_token(Token::Semicolon)(Tokens::new("a b c; d"));
+/// let (rest, raw) = result.unwrap();
+///
+/// let spans: Vec<_> = raw.iter().map(|(_, span, _)| span).collect();
+/// assert_eq!(spans, [0..1, 2..3, 4..5]);
+/// assert_eq!(rest.peek(), Some((Token::Semicolon, ";")));
+///
+/// let result: IResult<_, _> = take_until_token(Token::Semicolon)(Tokens::new("a b"));
+/// assert!(result.is_err());
+/// ```
+pub fn take_until_token<'i, T, E>(
+    until: T,
+) -> impl Fn(Tokens<'i, T>) -> IResult<Tokens<'i, T>, Tokens<'i, T>, E>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+{
+    move |input| {
+        let start = input.lexer.span().end;
+        let mut rest = input.clone();
+        loop {
+            match rest.peek_spanned() {
+                Some((t, span)) if t == until => {
+                    let taken = sub_tokens(&input, start, span.start);
+                    return Ok((rest, taken));
+                }
+                Some(_) => rest = rest.advance(),
+                None => {
+                    return Err(
+                        end_of_tokens(&rest, ErrorKind::TakeUntil).unwrap_or_else(|| {
+                            nom::Err::Error(E::from_error_kind(input, ErrorKind::TakeUntil))
+                        }),
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Returns a stream with the configuration of `input` over the tokens between the byte offsets
+/// `start` and `end`.
+fn sub_tokens<'i, T>(input: &Tokens<'i, T>, start: usize, end: usize) -> Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    input.with_lexer(input.lexer_at(start, end))
+}
+
+/// Parses zero or more `item`s separated by the token `sep`, allowing a trailing `sep`.
+///
+/// Parsing stops at the first token that can't start an `item`. A `sep` after the last item is
+/// consumed; a `sep` without a preceding item is not.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token(",")]
+/// #     Comma,
+/// #
+/// #     #[token(")")]
+/// #     RParen,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::separated_list_trailing, Tokens};
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let mut list = separated_list_trailing(parse_number, Token::Comma);
+///
+/// let (rest, items) = list(Tokens::new("1, 2, 3)")).unwrap();
+/// assert_eq!(items, [1, 2, 3]);
+/// assert_eq!(rest.peek(), Some((Token::RParen, ")")));
+///
+/// let (rest, items) = list(Tokens::new("1, 2, 3,)")).unwrap();
+/// assert_eq!(items, [1, 2, 3]);
+/// assert_eq!(rest.peek(), Some((Token::RParen, ")")));
+///
+/// let (rest, items) = list(Tokens::new(", 1")).unwrap();
+/// assert!(items.is_empty());
+/// assert_eq!(rest.peek(), Some((Token::Comma, ",")));
+/// ```
+pub fn separated_list_trailing<'i, T, O, E, P>(
+    mut item: P,
+    sep: T,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, Vec<O>, E>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+{
+    move |mut input| {
+        let mut items = Vec::new();
+        loop {
+            match item.parse(input.clone()) {
+                Ok((rest, o)) => {
+                    items.push(o);
+                    input = rest;
+                }
+                Err(nom::Err::Error(_)) => return Ok((input, items)),
+                Err(e) => return Err(e),
+            }
+            match input.clone().next_token() {
+                (rest, Some((t, _, _))) if t == sep => input = rest,
+                _ => return Ok((input, items)),
+            }
+        }
+    }
+}
+
+/// Runs `parser` with a different token type, then switches back to the original token type
+/// after the tokens it consumed.
+///
+/// The input is converted with [`Tokens::morph`], and the configuration of the original input
+/// is restored afterwards. Lexing errors collected inside the mode are converted back with
+/// [`Into`] and kept. If `parser` fails, its error is wrapped with [`FromExternalError`],
+/// pointing at the position where the mode was entered.
+///
+/// ### Example
+///
+/// ```
+/// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// enum Template {
+///     #[regex("[^{]+")]
+///     Text,
+///
+///     #[token("{{")]
+///     Open,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// #[logos(skip r"[ \t\n\f]+")]
+/// enum Code {
+///     #[regex("[a-z]+")]
+///     Ident,
+///
+///     #[token("}}")]
+///     Close,
+/// }
+///
+/// use logos_nom_bridge::{combinators::with_mode, LexErrorPolicy, Tokens};
+/// use nom::{branch::alt, multi::many0, sequence::{preceded, terminated}, IResult, Parser};
+///
+/// logos_nom_bridge::token_parser!(token: Template);
+/// logos_nom_bridge::token_parser!(token: Code);
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Part<'i> {
+///     Text(&'i str),
+///     Var(&'i str),
+/// }
+///
+/// fn template(input: Tokens<'_, Template>) -> IResult<Tokens<'_, Template>, Vec<Part<'_>>> {
+///     many0(alt((
+///         Template::Text.map(Part::Text),
+///         preceded(Template::Open, with_mode(terminated(Code::Ident, Code::Close))).map(Part::Var),
+///     )))(input)
+/// }
+///
+/// let (rest, parts) = template(Tokens::new("Hello {{ name }}!")).unwrap();
+/// assert_eq!(parts, [Part::Text("Hello "), Part::Var("name"), Part::Text("!")]);
+/// assert!(rest.is_empty());
+///
+/// let tokens = Tokens::new("Hi {{ a $ }}").on_lex_error(LexErrorPolicy::Collect);
+/// let (rest, _) = template(tokens).unwrap();
+/// assert_eq!(rest.lex_error_slices(), [(8..9, "$")]);
+///
+/// let result = template(Tokens::new("Hi {{ name").streaming());
+/// assert!(matches!(result, Err(nom::Err::Incomplete(_))));
+/// ```
+pub fn with_mode<'i, T, U, O, E, F, P>(
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, O, E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone + Into<U::Extras>,
+    T::Error: Into<U::Error>,
+    U: Logos<'i, Source = T::Source>,
+    U::Extras: Into<T::Extras>,
+    U::Error: Into<T::Error>,
+    P: Parser<Tokens<'i, U>, O, F>,
+    E: FromExternalError<Tokens<'i, T>, F>,
+{
+    move |input| match parser.parse(input.clone().morph()) {
+        Ok((rest, o)) => {
+            let mut lex_errors = input.lex_errors.clone();
+            let n = LexErrors::len(&rest.lex_errors) - LexErrors::len(&lex_errors);
+            LexErrors::append(&mut lex_errors, &rest.lex_errors, n);
+            let (consumed, span) = (rest.consumed, rest.current_span());
+            let mut rest = input.with_lexer(rest.into_inner().morph());
+            rest.consumed = consumed;
+            rest.span = span;
+            rest.lex_errors = lex_errors;
+            // the synthetic tokens computed before the mode are stale
+            rest.inserted = None;
+            Ok((rest, o))
+        }
+        Err(nom::Err::Error(e)) => Err(nom::Err::Error(E::from_external_error(
+            input,
+            ErrorKind::Verify,
+            e,
+        ))),
+        Err(nom::Err::Failure(e)) => Err(nom::Err::Failure(E::from_external_error(
+            input,
+            ErrorKind::Verify,
+            e,
+        ))),
+        Err(nom::Err::Incomplete(n)) => Err(nom::Err::Incomplete(n)),
+    }
+}
+
+/// Runs `span` to obtain a span of the source, then lexes that span with a different token type
+/// using [`Tokens::sublex`] and runs `parser` on it.
+///
+/// The spans produced by `parser` are relative to the whole source. `parser` doesn't need to
+/// consume the whole span; combine it with [`end`] if it should. If `parser` fails, its error
+/// is wrapped with [`FromExternalError`], pointing at the input before `span` was run.
+///
+/// ### Example
+///
+/// ```
+/// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// #[logos(skip r"[ \t\n\f]+")]
+/// enum Token {
+///     #[regex(r#"f"[^"]*""#)]
+///     FString,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// enum Part {
+///     #[regex("[^{}]+")]
+///     Text,
+///
+///     #[token("{")]
+///     LBrace,
+///
+///     #[token("}")]
+///     RBrace,
+/// }
+///
+/// use logos_nom_bridge::{
+///     combinators::{parse_within, token_span},
+///     Tokens,
+/// };
+/// use nom::{branch::alt, multi::many0, sequence::delimited, IResult, Parser};
+///
+/// let content = token_span(Token::FString).map(|span| span.start + 2..span.end - 1);
+/// let part = alt((
+///     token_span(Part::Text),
+///     delimited(token_span(Part::LBrace), token_span(Part::Text), token_span(Part::RBrace)),
+/// ));
+///
+/// let parts = many0::<_, _, nom::error::Error<_>, _>(part);
+///
+/// let result: IResult<_, _> = parse_within(content, parts)(Tokens::new(r#"f"a {b}""#));
+/// let (rest, spans) = result.unwrap();
+///
+/// assert_eq!(spans, [2..4, 5..6]);
+/// assert!(rest.is_empty());
+/// ```
+pub fn parse_within<'i, T, U, O, E, F, S, P>(
+    mut span: S,
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, O, E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    U: Logos<'i, Source = T::Source>,
+    U::Extras: Default,
+    S: Parser<Tokens<'i, T>, Span, E>,
+    P: Parser<Tokens<'i, U>, O, F>,
+    E: FromExternalError<Tokens<'i, T>, F>,
+{
+    move |input| {
+        let (rest, span) = span.parse(input.clone())?;
+        match parser.parse(input.sublex(span)) {
+            Ok((_, o)) => Ok((rest, o)),
+            Err(nom::Err::Error(e)) => Err(nom::Err::Error(E::from_external_error(
+                input,
+                ErrorKind::Verify,
+                e,
+            ))),
+            Err(nom::Err::Failure(e)) => Err(nom::Err::Failure(E::from_external_error(
+                input,
+                ErrorKind::Verify,
+                e,
+            ))),
+            Err(nom::Err::Incomplete(n)) => Err(nom::Err::Incomplete(n)),
+        }
+    }
+}
+
+/// A table of parse results keyed by position, used by [`memoize`].
+///
+/// A table belongs to one rule of the grammar. Results are keyed by the byte offset where the
+/// rule was applied and the number of tokens consumed before, which tells apart positions
+/// around synthetic tokens. A table should only be used with one source, and not across changes
+/// to the lexer's extras that affect lexing.
+pub struct Memo<'i, T, O, E>
+where
+    T: Logos<'i>,
+{
+    table: RefCell<BTreeMap<(usize, usize), (Tokens<'i, T>, O)>>,
+    error: PhantomData<E>,
+}
+
+impl<'i, T, O, E> Memo<'i, T, O, E>
+where
+    T: Logos<'i>,
+{
+    pub fn new() -> Self {
+        Memo {
+            table: RefCell::new(BTreeMap::new()),
+            error: PhantomData,
+        }
+    }
+
+    /// Returns the number of cached results.
+    pub fn len(&self) -> usize {
+        self.table.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        self.table.borrow_mut().clear();
+    }
+}
+
+impl<'i, T, O, E> Default for Memo<'i, T, O, E>
+where
+    T: Logos<'i>,
+{
+    fn default() -> Self {
+        Memo::new()
+    }
+}
+
+/// Caches the results of `parser` in `memo`, so it runs at most once per position (packrat
+/// parsing).
+///
+/// This makes grammars that try the same rule at the same position many times, e.g. because of
+/// ambiguous alternatives, run in linear time. Only successful results are cached, because
+/// errors usually can't be cloned; a rule that fails is run again the next time. Since the table
+/// is owned by the caller, the returned parser can be created anew on every call, as is common
+/// in recursive grammars.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[token("-")]
+/// #     Minus,
+/// #
+/// #     #[regex(r"[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// #
+/// #     Newline,
+/// #     Indent,
+/// #     Dedent,
+/// # }
+/// #
+/// use logos_nom_bridge::{
+///     combinators::{memoize, Memo},
+///     Tokens,
+/// };
+/// use nom::{branch::alt, sequence::separated_pair, IResult, Parser};
+/// use std::cell::Cell;
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// type Input<'i> = Tokens<'i, Token>;
+///
+/// fn number(input: Input<'_>) -> IResult<Input<'_>, i64> {
+///     match input.clone().next_token() {
+///         (rest, Some((Token::Number(n), _, _))) => Ok((rest, n)),
+///         _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Digit))),
+///     }
+/// }
+///
+/// let calls = Cell::new(0);
+/// let memo = Memo::new();
+/// let operand = |input| {
+///     memoize(&memo, |i| {
+///         calls.set(calls.get() + 1);
+///         number(i)
+///     })
+///     .parse(input)
+/// };
+///
+/// let result: IResult<_, _> = alt((
+///     separated_pair(operand, Token::Plus, operand).map(|(a, b)| a + b),
+///     separated_pair(operand, Token::Minus, operand).map(|(a, b)| a - b),
+/// ))(Tokens::new("5 - 3"));
+///
+/// assert_eq!(result.unwrap().1, 2);
+/// assert_eq!(calls.get(), 2);
+///
+/// // synthetic tokens don't advance in the source, but still count as a new position
+/// use logos_nom_bridge::combinators::{any_token, repeat_to_eof};
+///
+/// let memo = Memo::new();
+/// let tokens = Tokens::new("1\n2").with_layout(Token::Newline, Token::Indent, Token::Dedent);
+/// let result: IResult<_, _> =
+///     repeat_to_eof(|i| memoize(&memo, any_token.map(|(t, _, _)| t)).parse(i))(tokens);
+///
+/// use Token::*;
+/// assert_eq!(result.unwrap().1, [Number(1), Newline, Number(2), Newline]);
+/// assert_eq!(memo.len(), 4);
+/// ```
+pub fn memoize<'m, 'i, T, O, E, P>(
+    memo: &'m Memo<'i, T, O, E>,
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, O, E> + 'm
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    O: Clone,
+    P: Parser<Tokens<'i, T>, O, E> + 'm,
+{
+    move |input| {
+        let pos = (input.lexer.span().end, input.consumed);
+        if let Some(result) = memo.table.borrow().get(&pos) {
+            return Ok(result.clone());
+        }
+        let (rest, o) = parser.parse(input)?;
+        memo.table
+            .borrow_mut()
+            .insert(pos, (rest.clone(), o.clone()));
+        Ok((rest, o))
+    }
+}
+
+/// Prints `label`, the error and the tokens that `parser` was applied to, with their spans,
+/// when `parser` fails, and returns the result unchanged. This is the token-aware counterpart of
+/// [`nom::error::dbg_dmp`].
+///
+/// The number of printed tokens can be set with [`Tokens::with_debug_preview`].
+///
+/// Requires the `std` feature.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{combinators::dbg_tokens, Tokens};
+/// use nom::{sequence::pair, IResult};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// let mut plus_plus = dbg_tokens("plus_plus", pair(Token::Plus, Token::Plus));
+///
+/// // prints:
+/// // plus_plus: Error(Error { input: Tokens[Number(2) "2"], code: IsA })
+/// //   Plus at 0..1: "+"
+/// //   Number(2) at 3..4: "2"
+/// let result: IResult<_, _> = plus_plus(Tokens::new("+  2"));
+/// assert!(result.is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn dbg_tokens<'i, T, O, E, P>(
+    label: &'static str,
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, O, E>
+where
+    T: Logos<'i> + Clone + fmt::Debug,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    SourceSlice<'i, T>: fmt::Debug,
+    E: fmt::Debug,
+    P: Parser<Tokens<'i, T>, O, E>,
+{
+    move |input| {
+        let result = parser.parse(input.clone());
+        if let Err(e) = &result {
+            std::println!("{label}: {e:?}");
+            for (token, span, slice) in input.iter().take(input.hooks.debug_preview) {
+                std::println!("  {token:?} at {}..{}: {slice:?}", span.start, span.end);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,424 @@
+//! Rendering of error messages with a snippet of the source code.
+//!
+//! With the `miette` feature, diagnostics can be converted to [`miette::Report`]s, and
+//! [`TokenError`] implements [`miette::Diagnostic`]. With the `ariadne` feature, they can be
+//! converted to [`ariadne::Report`]s. With the `codespan-reporting` feature, they can be
+//! converted to [`codespan_reporting::diagnostic::Diagnostic`]s, and [`SourceFile`] can be used
+//! to emit them.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+
+use logos::Span;
+
+use crate::{
+    error::{ExpectedList, TokenError},
+    line_index::LineIndex,
+};
+
+/// An error message for a span of the source code, which can be rendered in the style of rustc.
+///
+/// ### Example
+///
+/// ```
+/// use logos_nom_bridge::diagnostics::Diagnostic;
+///
+/// let source = "let a = 1;\nlet b = a +;\n";
+/// let diagnostic = Diagnostic::new("expected expression, found `;`", 21..22)
+///     .with_label("expected expression")
+///     .with_note("`+` requires two operands");
+///
+/// assert_eq!(
+///     diagnostic.render(source),
+///     "\
+/// error: expected expression, found `;`
+///  --> 2:11
+///   |
+/// 2 | let b = a +;
+///   |           ^ expected expression
+///   |
+///   = note: `+` requires two operands
+/// ",
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The main message.
+    pub message: String,
+    /// The span of the source code the message refers to.
+    pub span: Span,
+    /// A message shown next to the underlined span.
+    pub label: Option<String>,
+    /// Additional messages shown below the snippet.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            message: message.into(),
+            span,
+            label: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Sets the message shown next to the underlined span.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Adds a note shown below the snippet.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Renders the diagnostic with the line of `source` that contains the start of the span.
+    /// The span is underlined up to the end of that line.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = String::new();
+        let _ = self.write_to(&mut out, source);
+        out
+    }
+
+    fn write_to(&self, out: &mut String, source: &str) -> fmt::Result {
+        let start = self.span.start.min(source.len());
+        let end = self.span.end.clamp(start, source.len());
+        let (line, column) = LineIndex::new(source).line_col(start);
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+        let underlined = source[start..end.min(line_end)].chars().count().max(1);
+
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        writeln!(out, "error: {}", self.message)?;
+        writeln!(out, "{gutter}--> {line}:{column}")?;
+        writeln!(out, "{gutter} |")?;
+        writeln!(out, "{number} | {text}")?;
+        write!(
+            out,
+            "{gutter} | {}{}",
+            " ".repeat(column - 1),
+            "^".repeat(underlined)
+        )?;
+        match &self.label {
+            Some(label) => writeln!(out, " {label}")?,
+            None => writeln!(out)?,
+        }
+        if !self.notes.is_empty() {
+            writeln!(out, "{gutter} |")?;
+            for note in &self.notes {
+                writeln!(out, "{gutter} = note: {note}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Converts a [`TokenError`] to a diagnostic that underlines the found token. The contexts of
+/// the error are added as notes.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{diagnostics::Diagnostic, error::TokenError, Tokens};
+/// use nom::{error::context, sequence::tuple, IResult};
+///
+/// logos_nom_bridge::token_parser!(
+///     token: Token,
+///     error<'i>(input, token): TokenError<'i, Token> =
+///         TokenError::expected_token(input, token.clone()),
+/// );
+///
+/// let source = "1 + +";
+/// let mut sum = context("sum", tuple((Token::Number(1), Token::Plus, Token::Number(2))));
+/// let result: IResult<_, _, _> = sum(Tokens::new(source));
+/// let nom::Err::Error(error) = result.unwrap_err() else { panic!() };
+///
+/// assert_eq!(
+///     Diagnostic::from(error).render(source),
+///     "\
+/// error: expected Number(2), found `+`
+///  --> 1:5
+///   |
+/// 1 | 1 + +
+///   |     ^ expected Number(2)
+///   |
+///   = note: while parsing sum
+/// ",
+/// );
+/// ```
+impl<T: fmt::Debug> From<TokenError<'_, T>> for Diagnostic {
+    fn from(error: TokenError<'_, T>) -> Self {
+        let expected = ExpectedList(&error.expected);
+        let message = match error.found {
+            Some(_) => format!("expected {expected}, found `{}`", error.slice),
+            None if error.lex_error => {
+                format!("expected {expected}, found invalid token `{}`", error.slice)
+            }
+            None => format!("expected {expected}, found end of input"),
+        };
+        let mut diagnostic =
+            Diagnostic::new(message, error.span).with_label(format!("expected {expected}"));
+        for ctx in error.context {
+            diagnostic = diagnostic.with_note(format!("while parsing {ctx}"));
+        }
+        diagnostic
+    }
+}
+
+/// Converts a span to a [`miette::SourceSpan`].
+///
+/// Requires the `miette` feature.
+#[cfg(feature = "miette")]
+pub fn source_span(span: Span) -> miette::SourceSpan {
+    span.into()
+}
+
+/// Creates a [`miette::NamedSource`] from a copy of `source`, so it can be attached to a
+/// [`miette::Report`] that outlives the source.
+///
+/// Requires the `miette` feature.
+#[cfg(feature = "miette")]
+pub fn named_source(name: impl AsRef<str>, source: &str) -> miette::NamedSource<String> {
+    miette::NamedSource::new(name, source.to_string())
+}
+
+#[cfg(feature = "miette")]
+impl Diagnostic {
+    /// Converts the diagnostic to a [`miette::Report`] with a copy of the source, so it can be
+    /// returned with `?` from functions that return a [`miette::Result`].
+    ///
+    /// Requires the `miette` feature.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use logos_nom_bridge::diagnostics::Diagnostic;
+    ///
+    /// fn check(source: &str) -> miette::Result<()> {
+    ///     if let Some(i) = source.find(';') {
+    ///         let diagnostic = Diagnostic::new("unexpected `;`", i..i + 1).with_label("here");
+    ///         return Err(diagnostic.into_report("input.txt", source));
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// let report = check("a + ;").unwrap_err();
+    /// assert_eq!(report.to_string(), "unexpected `;`");
+    ///
+    /// let label = report.labels().unwrap().next().unwrap();
+    /// assert_eq!((label.offset(), label.len(), label.label()), (4, 1, Some("here")));
+    /// ```
+    pub fn into_report(self, name: impl AsRef<str>, source: &str) -> miette::Report {
+        miette::Report::new(miette::MietteDiagnostic::from(self))
+            .with_source_code(named_source(name, source))
+    }
+}
+
+/// Converts the diagnostic with its label. The notes are joined to the help text.
+///
+/// Requires the `miette` feature.
+#[cfg(feature = "miette")]
+impl From<Diagnostic> for miette::MietteDiagnostic {
+    fn from(diagnostic: Diagnostic) -> Self {
+        let label = miette::LabeledSpan::new_primary_with_span(
+            diagnostic.label,
+            source_span(diagnostic.span),
+        );
+        let mut result = miette::MietteDiagnostic::new(diagnostic.message).with_label(label);
+        if !diagnostic.notes.is_empty() {
+            result = result.with_help(diagnostic.notes.join("\n"));
+        }
+        result
+    }
+}
+
+#[cfg(feature = "ariadne")]
+impl Diagnostic {
+    /// Creates an [`ariadne::Report`] builder for the diagnostic, with a label on its span. The
+    /// notes are added as notes. More labels can be added to the builder before it is finished.
+    ///
+    /// `source_id` identifies the source in the [`ariadne::Cache`] the report is printed with.
+    /// A [`TokenError`] can be converted to a `Diagnostic` first; its label then lists all
+    /// expected tokens.
+    ///
+    /// Requires the `ariadne` feature.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use logos_nom_bridge::diagnostics::Diagnostic;
+    ///
+    /// let source = "let a = 1;\nlet b = a +;\n";
+    /// let report = Diagnostic::new("expected expression, found `;`", 21..22)
+    ///     .with_label("expected expression")
+    ///     .to_ariadne("input.txt")
+    ///     .with_config(ariadne::Config::default().with_color(false))
+    ///     .finish();
+    ///
+    /// let mut out = Vec::new();
+    /// report.write(("input.txt", ariadne::Source::from(source)), &mut out).unwrap();
+    /// let out = String::from_utf8(out).unwrap();
+    ///
+    /// assert!(out.contains("Error: expected expression, found `;`"));
+    /// assert!(out.contains("input.txt:2:11"));
+    /// assert!(out.contains("expected expression"));
+    /// ```
+    pub fn to_ariadne<'a, Id>(&self, source_id: Id) -> ariadne::ReportBuilder<'a, (Id, Span)>
+    where
+        Id: fmt::Debug + core::hash::Hash + Eq + alloc::borrow::ToOwned + Clone,
+    {
+        let span = (source_id, self.span.clone());
+        let mut label = ariadne::Label::new(span.clone());
+        if let Some(message) = &self.label {
+            label = label.with_message(message);
+        }
+        let mut builder = ariadne::Report::build(ariadne::ReportKind::Error, span)
+            .with_message(&self.message)
+            .with_label(label);
+        builder.with_notes(&self.notes);
+        builder
+    }
+}
+
+#[cfg(feature = "codespan-reporting")]
+impl Diagnostic {
+    /// Converts the diagnostic to a [`codespan_reporting::diagnostic::Diagnostic`] with a
+    /// primary label on its span in the file `file_id`. The notes are added as notes.
+    ///
+    /// Requires the `codespan-reporting` feature.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use codespan_reporting::term::{self, termcolor::NoColor};
+    /// use logos_nom_bridge::diagnostics::{Diagnostic, SourceFile};
+    ///
+    /// let source = "let a = 1;\nlet b = a +;\n";
+    /// let diagnostic = Diagnostic::new("expected expression, found `;`", 21..22)
+    ///     .with_label("expected expression")
+    ///     .to_codespan(());
+    ///
+    /// let file = SourceFile::new("input.txt", source);
+    /// let mut out = NoColor::new(Vec::new());
+    /// term::emit(&mut out, &term::Config::default(), &file, &diagnostic).unwrap();
+    /// let out = String::from_utf8(out.into_inner()).unwrap();
+    ///
+    /// assert!(out.starts_with("error: expected expression, found `;`"));
+    /// assert!(out.contains("input.txt:2:11"));
+    /// assert!(out.contains("^ expected expression"));
+    /// ```
+    pub fn to_codespan<FileId>(
+        &self,
+        file_id: FileId,
+    ) -> codespan_reporting::diagnostic::Diagnostic<FileId> {
+        let mut label = codespan_reporting::diagnostic::Label::primary(file_id, self.span.clone());
+        if let Some(message) = &self.label {
+            label = label.with_message(message);
+        }
+        codespan_reporting::diagnostic::Diagnostic::error()
+            .with_message(&self.message)
+            .with_labels(alloc::vec![label])
+            .with_notes(self.notes.clone())
+    }
+}
+
+/// A named source string that implements [`codespan_reporting::files::Files`], so diagnostics
+/// can be emitted without copying the source into a `SimpleFiles` database. The file id is `()`.
+///
+/// Requires the `codespan-reporting` feature. See [`Diagnostic::to_codespan`] for an example.
+#[cfg(feature = "codespan-reporting")]
+#[derive(Debug, Clone)]
+pub struct SourceFile<'i, N = &'i str> {
+    name: N,
+    index: LineIndex<'i>,
+}
+
+#[cfg(feature = "codespan-reporting")]
+impl<'i, N> SourceFile<'i, N> {
+    pub fn new(name: N, source: &'i str) -> Self {
+        SourceFile {
+            name,
+            index: LineIndex::new(source),
+        }
+    }
+
+    /// Returns the name of the file.
+    pub fn name(&self) -> &N {
+        &self.name
+    }
+
+    /// Returns the source string.
+    pub fn source(&self) -> &'i str {
+        self.index.source()
+    }
+}
+
+#[cfg(feature = "codespan-reporting")]
+impl<'a, 'i: 'a, N> codespan_reporting::files::Files<'a> for SourceFile<'i, N>
+where
+    N: 'a + fmt::Display + Clone,
+{
+    type FileId = ();
+    type Name = N;
+    type Source = &'i str;
+
+    fn name(&'a self, (): ()) -> Result<N, codespan_reporting::files::Error> {
+        Ok(self.name.clone())
+    }
+
+    fn source(&'a self, (): ()) -> Result<&'i str, codespan_reporting::files::Error> {
+        Ok(self.index.source())
+    }
+
+    fn line_index(
+        &'a self,
+        (): (),
+        byte_index: usize,
+    ) -> Result<usize, codespan_reporting::files::Error> {
+        Ok(self.index.line_col(byte_index).0 - 1)
+    }
+
+    fn line_range(
+        &'a self,
+        (): (),
+        line_index: usize,
+    ) -> Result<core::ops::Range<usize>, codespan_reporting::files::Error> {
+        let starts = self.index.line_starts();
+        let source_len = self.index.source().len();
+        match starts.get(line_index) {
+            Some(&start) => {
+                let end = starts.get(line_index + 1).copied().unwrap_or(source_len);
+                Ok(start..end)
+            }
+            // codespan-reporting asks for the line after the last one
+            None if line_index == starts.len() => Ok(source_len..source_len),
+            None => Err(codespan_reporting::files::Error::LineTooLarge {
+                given: line_index,
+                max: starts.len() - 1,
+            }),
+        }
+    }
+}
@@ -0,0 +1,601 @@
+//! Error types for parsers over [`Tokens`].
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    cmp::Ordering,
+    fmt::{self, Write},
+};
+
+use logos::{Logos, Span};
+use nom::error::{
+    ContextError, ErrorKind, FromExternalError, ParseError, VerboseError, VerboseErrorKind,
+};
+
+use crate::Tokens;
+
+/// A parse error that records what was expected and which token was found instead.
+///
+/// It implements [`ParseError`], so it can be used with all nom combinators. Errors created by
+/// nom only know the [`ErrorKind`] of the failed parser; use [`TokenError::expected_token`] and
+/// [`TokenError::expected_label`] in the [`token_parser`](crate::token_parser) and
+/// [`data_variant_parser`](crate::data_variant_parser) macros to record the expected token.
+///
+/// It also implements [`ContextError`], so the names passed to [`nom::error::context`] are
+/// collected in [`TokenError::context`] as the error propagates.
+///
+/// When the branches of [`nom::branch::alt`] fail, the error of the branch that got furthest is
+/// kept. If several branches failed at the same position, their expectations are merged, so
+/// the error lists everything that was expected there.
+///
+/// The error keeps the source slice of the found token for its [`Display`](fmt::Display) impl,
+/// so it only supports token types whose source is a `str`. With a byte source, use
+/// [`nom::error::Error`] or [`VerboseError`] instead.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[token(")")]
+/// #     RParen,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{
+///     error::{Expected, TokenError},
+///     Tokens,
+/// };
+/// use nom::{sequence::tuple, IResult};
+///
+/// logos_nom_bridge::token_parser!(
+///     token: Token,
+///     error<'i>(input, token): TokenError<'i, Token> =
+///         TokenError::expected_token(input, token.clone()),
+/// );
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn number<'i>(input) -> Result<i64, TokenError<'i, Token>>;
+///
+///     pattern = Token::Number(n) => n;
+///     error = TokenError::expected_label(input, "number");
+/// }
+///
+/// let result: IResult<_, _, _> = tuple((number, Token::Plus, number))(Tokens::new("1 + )"));
+/// let nom::Err::Error(error) = result.unwrap_err() else { panic!() };
+///
+/// assert_eq!(error.expected, [Expected::Label("number")]);
+/// assert_eq!(error.found, Some(Token::RParen));
+/// assert_eq!((error.span.clone(), error.slice), (4..5, ")"));
+/// assert_eq!(error.to_string(), "expected number, found `)` at 4..5");
+///
+/// // when the tokens stop at a lexing error, it is reported instead of the end of the input
+/// let result: IResult<_, _, _> = tuple((number, Token::Plus, number))(Tokens::new("1 + $ 2"));
+/// let nom::Err::Error(error) = result.unwrap_err() else { panic!() };
+///
+/// assert_eq!((&error.found, error.lex_error), (&None, true));
+/// assert_eq!(error.to_string(), "expected number, found invalid token `$` at 4..5");
+/// ```
+///
+/// With [`nom::branch::alt`]:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[token("-")]
+/// #     Minus,
+/// #
+/// #     #[token(")")]
+/// #     RParen,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// # use logos_nom_bridge::{error::TokenError, Tokens};
+/// # use nom::{
+/// #     branch::alt,
+/// #     sequence::{preceded, terminated},
+/// #     IResult,
+/// # };
+/// #
+/// # logos_nom_bridge::token_parser!(
+/// #     token: Token,
+/// #     error<'i>(input, token): TokenError<'i, Token> =
+/// #         TokenError::expected_token(input, token.clone()),
+/// # );
+/// #
+/// # logos_nom_bridge::data_variant_parser! {
+/// #     fn number<'i>(input) -> Result<i64, TokenError<'i, Token>>;
+/// #
+/// #     pattern = Token::Number(n) => n;
+/// #     error = TokenError::expected_label(input, "number");
+/// # }
+/// #
+/// let mut operand = alt((
+///     preceded(Token::Minus, number),
+///     preceded(Token::Plus, number),
+///     terminated(number, Token::RParen),
+/// ));
+///
+/// let result: IResult<_, _, _> = operand(Tokens::new(") 1"));
+/// let nom::Err::Error(error) = result.unwrap_err() else { panic!() };
+/// assert_eq!(
+///     error.to_string(),
+///     "expected Minus, Plus or number, found `)` at 0..1",
+/// );
+///
+/// // `- +` gets further than the other branches
+/// let result: IResult<_, _, _> = operand(Tokens::new("- +"));
+/// let nom::Err::Error(error) = result.unwrap_err() else { panic!() };
+/// assert_eq!(error.to_string(), "expected number, found `+` at 2..3");
+/// ```
+///
+/// With [`nom::error::context`]:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("(")]
+/// #     LParen,
+/// #
+/// #     #[token(")")]
+/// #     RParen,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Ident,
+/// # }
+/// #
+/// use logos_nom_bridge::{error::TokenError, Tokens};
+/// use nom::{error::context, sequence::tuple, IResult};
+///
+/// logos_nom_bridge::token_parser!(
+///     token: Token,
+///     error<'i>(input, token): TokenError<'i, Token> =
+///         TokenError::expected_token(input, token.clone()),
+/// );
+///
+/// type Input<'i> = Tokens<'i, Token>;
+///
+/// fn params(input: Input<'_>) -> IResult<Input<'_>, (), TokenError<'_, Token>> {
+///     let (input, _) = context("parameters", tuple((Token::LParen, Token::RParen)))(input)?;
+///     Ok((input, ()))
+/// }
+///
+/// fn signature(input: Input<'_>) -> IResult<Input<'_>, (), TokenError<'_, Token>> {
+///     let (input, _) = context("function signature", tuple((Token::Ident, params)))(input)?;
+///     Ok((input, ()))
+/// }
+///
+/// let nom::Err::Error(error) = signature(Tokens::new("f(x)")).unwrap_err() else { panic!() };
+/// assert_eq!(error.context, ["parameters", "function signature"]);
+/// assert_eq!(
+///     error.to_string(),
+///     "expected RParen, found `x` at 2..3 while parsing parameters, function signature",
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenError<'i, T> {
+    /// What the failed parser expected. This contains more than one item when several parsers
+    /// failed at the same position.
+    pub expected: Vec<Expected<T>>,
+    /// The token that was found instead, or `None` at the end of the input or at a lexing
+    /// error.
+    pub found: Option<T>,
+    /// Whether the tokens stop at a lexing error here. See [`Tokens::lex_error`].
+    pub lex_error: bool,
+    /// The span of the found token or lexing error. At the end of the input, this is an empty
+    /// span at the end of the source.
+    pub span: Span,
+    /// The source slice of the found token or lexing error.
+    pub slice: &'i str,
+    /// The contexts added with [`nom::error::context`], from the innermost to the outermost.
+    pub context: Vec<&'static str>,
+}
+
+/// What a parser expected. See [`TokenError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected<T> {
+    /// A specific token.
+    Token(T),
+    /// A description such as "number" or "expression".
+    Label(&'static str),
+    /// The kind of nom parser that failed. This is used for errors created by nom.
+    Kind(ErrorKind),
+}
+
+impl<'i, T> TokenError<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    /// Creates an error at the start of `input`.
+    pub fn new(input: &Tokens<'i, T>, expected: Expected<T>) -> Self {
+        let (found, lex_error, span, slice) = found(input);
+        TokenError {
+            expected: alloc::vec![expected],
+            found,
+            lex_error,
+            span,
+            slice,
+            context: Vec::new(),
+        }
+    }
+
+    /// Creates an error at the start of `input` that expected `token`.
+    pub fn expected_token(input: Tokens<'i, T>, token: T) -> Self {
+        Self::new(&input, Expected::Token(token))
+    }
+
+    /// Creates an error at the start of `input` that expected something described by `label`.
+    pub fn expected_label(input: Tokens<'i, T>, label: &'static str) -> Self {
+        Self::new(&input, Expected::Label(label))
+    }
+
+    /// Creates an error at the start of `input` that expected one of `tokens`.
+    pub fn expected_one_of(input: Tokens<'i, T>, tokens: impl IntoIterator<Item = T>) -> Self {
+        let mut error = Self::new(&input, Expected::Kind(ErrorKind::OneOf));
+        error.expected = tokens.into_iter().map(Expected::Token).collect();
+        error
+    }
+}
+
+impl<'i, T> ParseError<Tokens<'i, T>> for TokenError<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone + PartialEq,
+    T::Extras: Clone,
+{
+    fn from_error_kind(input: Tokens<'i, T>, kind: ErrorKind) -> Self {
+        Self::new(&input, Expected::Kind(kind))
+    }
+
+    fn append(_: Tokens<'i, T>, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(mut self, other: Self) -> Self {
+        match self.span.start.cmp(&other.span.start) {
+            Ordering::Less => other,
+            Ordering::Greater => self,
+            Ordering::Equal => {
+                for expected in other.expected {
+                    if !self.expected.contains(&expected) {
+                        self.expected.push(expected);
+                    }
+                }
+                self
+            }
+        }
+    }
+}
+
+impl<'i, T> ContextError<Tokens<'i, T>> for TokenError<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    fn add_context(_: Tokens<'i, T>, ctx: &'static str, mut other: Self) -> Self {
+        other.context.push(ctx);
+        other
+    }
+}
+
+impl<'i, T, E> FromExternalError<Tokens<'i, T>, E> for TokenError<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    fn from_external_error(input: Tokens<'i, T>, kind: ErrorKind, _: E) -> Self {
+        Self::new(&input, Expected::Kind(kind))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for Expected<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Token(token) => write!(f, "{token:?}"),
+            Expected::Label(label) => f.write_str(label),
+            Expected::Kind(ErrorKind::Eof) => f.write_str("end of input"),
+            Expected::Kind(kind) => write!(f, "{}", kind.description()),
+        }
+    }
+}
+
+/// Formats a list of expectations as "a, b or c"
+pub(crate) struct ExpectedList<'a, T>(pub(crate) &'a [Expected<T>]);
+
+impl<T: fmt::Debug> fmt::Display for ExpectedList<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, expected) in self.0.iter().enumerate() {
+            match i {
+                0 => {}
+                _ if i == self.0.len() - 1 => f.write_str(" or ")?,
+                _ => f.write_str(", ")?,
+            }
+            write!(f, "{expected}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for TokenError<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}", ExpectedList(&self.expected))?;
+        match self.found {
+            Some(_) => write!(
+                f,
+                ", found `{}` at {}..{}",
+                self.slice, self.span.start, self.span.end
+            ),
+            None if self.lex_error => write!(
+                f,
+                ", found invalid token `{}` at {}..{}",
+                self.slice, self.span.start, self.span.end
+            ),
+            None => write!(f, ", found end of input at {}", self.span.start),
+        }?;
+        for (i, ctx) in self.context.iter().enumerate() {
+            let sep = if i == 0 { " while parsing " } else { ", " };
+            write!(f, "{sep}{ctx}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for TokenError<'_, T> {}
+
+/// Labels the found token with what was expected. The contexts are shown as help text.
+///
+/// Requires the `miette` feature. The source code must be attached to the report, e.g. with
+/// [`miette::Report::with_source_code`]; to return the error from a function, it can be
+/// converted to a [`Diagnostic`](crate::diagnostics::Diagnostic) first.
+#[cfg(feature = "miette")]
+impl<T: fmt::Debug> miette::Diagnostic for TokenError<'_, T> {
+    fn help<'a>(&'a self) -> Option<alloc::boxed::Box<dyn fmt::Display + 'a>> {
+        if self.context.is_empty() {
+            return None;
+        }
+        let help = alloc::format!("while parsing {}", self.context.join(", "));
+        Some(alloc::boxed::Box::new(help))
+    }
+
+    fn labels(&self) -> Option<alloc::boxed::Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let label = miette::LabeledSpan::new_primary_with_span(
+            Some(alloc::format!("expected {}", ExpectedList(&self.expected))),
+            self.span.clone(),
+        );
+        Some(alloc::boxed::Box::new(core::iter::once(label)))
+    }
+}
+
+/// Renders a [`VerboseError`] with one line per frame, starting with the innermost frame. Each
+/// line contains the line and column of the token where the frame's input starts.
+///
+/// Unlike the [`Debug`](fmt::Debug) output of the error, this doesn't print the remaining
+/// input of each frame.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("(")]
+/// #     LParen,
+/// #
+/// #     #[token(")")]
+/// #     RParen,
+/// #
+/// #     #[regex("[a-z]+")]
+/// #     Ident,
+/// # }
+/// #
+/// use logos_nom_bridge::{error::convert_verbose_error, Tokens};
+/// use nom::{
+///     error::{context, ErrorKind, ParseError, VerboseError},
+///     sequence::tuple,
+///     IResult,
+/// };
+///
+/// logos_nom_bridge::token_parser!(
+///     token: Token,
+///     error<'i>(input, token): VerboseError<Tokens<'i, Token>> =
+///         VerboseError::from_error_kind(input, ErrorKind::Tag),
+/// );
+///
+/// let tokens = Tokens::new("f(\n  x)");
+/// let result: IResult<_, _, VerboseError<_>> =
+///     context("call", tuple((Token::Ident, Token::LParen, Token::RParen)))(tokens);
+/// let nom::Err::Error(error) = result.unwrap_err() else { panic!() };
+///
+/// assert_eq!(
+///     convert_verbose_error(&error),
+///     "2:3: expected Tag, found `x`\n1:1: in call, found `f`\n",
+/// );
+/// ```
+pub fn convert_verbose_error<'i, T>(error: &VerboseError<Tokens<'i, T>>) -> String
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    let mut out = String::new();
+    // a frame's source may be cut off by `InputTake`
+    let Some((input, _)) = error.errors.iter().max_by_key(|(i, _)| i.source().len()) else {
+        return out;
+    };
+    let index = input.line_index();
+    for (input, kind) in &error.errors {
+        let (found, lex_error, span, slice) = found(input);
+        let (line, col) = index.line_col(span.start);
+        let _ = write!(out, "{line}:{col}: ");
+        let _ = match kind {
+            VerboseErrorKind::Context(ctx) => write!(out, "in {ctx}"),
+            VerboseErrorKind::Char(c) => write!(out, "expected '{c}'"),
+            VerboseErrorKind::Nom(kind) => {
+                write!(out, "expected {}", Expected::<()>::Kind(*kind))
+            }
+        };
+        let _ = match found {
+            Some(_) => writeln!(out, ", found `{slice}`"),
+            None if lex_error => writeln!(out, ", found invalid token `{slice}`"),
+            None => writeln!(out, ", found end of input"),
+        };
+    }
+    out
+}
+
+/// Returns the first token of `input` with its span and slice, the lexing error at which the
+/// tokens stop (indicated by `true`), or an empty span at the end of the source
+fn found<'i, T>(input: &Tokens<'i, T>) -> (Option<T>, bool, Span, &'i str)
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    if let Some((token, span, slice)) = input.peek_with_span() {
+        return (Some(token), false, span, slice);
+    }
+    match input.lex_error() {
+        Some((_, span)) => (None, true, span.clone(), &input.source()[span]),
+        None => {
+            let end = input.source().len();
+            (None, false, end..end, "")
+        }
+    }
+}
+
+/// A [`nom_supreme::error::GenericErrorTree`] whose tags are tokens. The location `L` is the
+/// input by default; [`nom_supreme::final_parser::final_parser`] can convert it to a
+/// [`TokenLocation`].
+///
+/// Requires the `nom-supreme` feature. See [`expected_token_tree`] for an example.
+#[cfg(feature = "nom-supreme")]
+pub type TokenErrorTree<'i, T, L = Tokens<'i, T>> = nom_supreme::error::GenericErrorTree<
+    L,
+    T,
+    &'static str,
+    alloc::boxed::Box<dyn std::error::Error + Send + Sync + 'static>,
+>;
+
+/// Creates a [`TokenErrorTree`] at the start of `input` that expected `token`.
+///
+/// Requires the `nom-supreme` feature.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{
+///     error::{expected_token_tree, TokenErrorTree, TokenLocation},
+///     Tokens,
+/// };
+/// use nom::{error::context, sequence::tuple};
+/// use nom_supreme::final_parser::final_parser;
+///
+/// logos_nom_bridge::token_parser!(
+///     token: Token,
+///     error<'i>(input, token): TokenErrorTree<'i, Token> =
+///         expected_token_tree(input, token.clone()),
+/// );
+///
+/// let sum = tuple((Token::Number(1), Token::Plus, Token::Number(2)));
+/// let mut parser = final_parser(context("sum", sum));
+/// let result: Result<_, TokenErrorTree<Token, TokenLocation<Token>>> =
+///     parser(Tokens::new("1 +\n 3"));
+///
+/// assert_eq!(
+///     result.unwrap_err().to_string(),
+///     "in section \"sum\" at 1:1, found `1`,\nexpected Number(2) at 2:2, found `3`",
+/// );
+/// ```
+#[cfg(feature = "nom-supreme")]
+pub fn expected_token_tree<'i, T>(input: Tokens<'i, T>, token: T) -> TokenErrorTree<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    use nom_supreme::error::{BaseErrorKind, Expectation, GenericErrorTree};
+
+    GenericErrorTree::Base {
+        location: input,
+        kind: BaseErrorKind::Expected(Expectation::Tag(token)),
+    }
+}
+
+/// The position of an error in the source, with the token found there. It is created from the
+/// [`Tokens`] stored in an error by [`nom_supreme::final_parser::final_parser`]. Like
+/// [`TokenError`], it requires a `str` source: it stores the found slice as `&str`, and columns
+/// are counted in `char`s.
+///
+/// Requires the `nom-supreme` feature.
+#[cfg(feature = "nom-supreme")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenLocation<'i, T> {
+    /// The 1-based line number.
+    pub line: usize,
+    /// The 1-based column, counted in `char`s.
+    pub column: usize,
+    /// The token at this position, or `None` at the end of the input or at a lexing error.
+    pub found: Option<T>,
+    /// Whether the tokens stop at a lexing error here. See [`Tokens::lex_error`].
+    pub lex_error: bool,
+    /// The span of the token or lexing error. At the end of the input, this is an empty span
+    /// at the end of the source.
+    pub span: Span,
+    /// The source slice of the token or lexing error.
+    pub slice: &'i str,
+}
+
+#[cfg(feature = "nom-supreme")]
+impl<'i, T> nom_supreme::final_parser::RecreateContext<Tokens<'i, T>> for TokenLocation<'i, T>
+where
+    T: Logos<'i, Source = str> + Clone,
+    T::Extras: Clone,
+{
+    fn recreate_context(original_input: Tokens<'i, T>, tail: Tokens<'i, T>) -> Self {
+        let (found, lex_error, span, slice) = found(&tail);
+        let (line, column) = original_input.line_index().line_col(span.start);
+        TokenLocation {
+            line,
+            column,
+            found,
+            lex_error,
+            span,
+            slice,
+        }
+    }
+}
+
+#[cfg(feature = "nom-supreme")]
+impl<T> fmt::Display for TokenLocation<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.found {
+            Some(_) => write!(f, "{}:{}, found `{}`", self.line, self.column, self.slice),
+            None if self.lex_error => write!(
+                f,
+                "{}:{}, found invalid token `{}`",
+                self.line, self.column, self.slice
+            ),
+            None => write!(f, "{}:{}, found end of input", self.line, self.column),
+        }
+    }
+}
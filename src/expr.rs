@@ -0,0 +1,214 @@
+//! A precedence-climbing (Pratt) parser for operator expressions.
+//!
+//! ### Example
+//!
+//! ```
+//! # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+//! # #[logos(skip r"[ \t\n\f]+")]
+//! # enum Token {
+//! #     #[token("+")]
+//! #     Plus,
+//! #
+//! #     #[token("-")]
+//! #     Minus,
+//! #
+//! #     #[token("*")]
+//! #     Star,
+//! #
+//! #     #[token("^")]
+//! #     Caret,
+//! #
+//! #     #[token("!")]
+//! #     Bang,
+//! #
+//! #     #[regex(r"[0-9]+", |lex| lex.slice().parse().ok())]
+//! #     Number(i64),
+//! # }
+//! #
+//! use logos_nom_bridge::{
+//!     expr::{Assoc, ExprParser},
+//!     Tokens,
+//! };
+//! use nom::{IResult, Parser};
+//!
+//! logos_nom_bridge::data_variant_parser! {
+//!     fn parse_number(input) -> Result<i64>;
+//!     pattern = Token::Number(n) => n;
+//! }
+//!
+//! let mut expr = ExprParser::<_, i64, _>::new(parse_number)
+//!     .binary(Token::Plus, 1, Assoc::Left, |a, b| a + b)
+//!     .binary(Token::Minus, 1, Assoc::Left, |a, b| a - b)
+//!     .binary(Token::Star, 2, Assoc::Left, |a, b| a * b)
+//!     .binary(Token::Caret, 4, Assoc::Right, |a, b| a.pow(b as u32))
+//!     .prefix(Token::Minus, 3, |a| -a)
+//!     .postfix(Token::Bang, 5, |a| (1..=a).product());
+//!
+//! let result: IResult<_, _> = expr.parse(Tokens::new("1 + 2 * 3 - 4"));
+//! assert_eq!(result.unwrap().1, 3);
+//!
+//! let result: IResult<_, _> = expr.parse(Tokens::new("2 ^ 3 ^ 2"));
+//! assert_eq!(result.unwrap().1, 512);
+//!
+//! let result: IResult<_, _> = expr.parse(Tokens::new("-2 ^ 2 + 3! * 2"));
+//! assert_eq!(result.unwrap().1, 8);
+//!
+//! // on streaming input, an operator may still follow
+//! let result: IResult<_, _> = expr.parse(Tokens::new("1 + 2 ").streaming());
+//! assert!(matches!(result, Err(nom::Err::Incomplete(_))));
+//!
+//! // an expression can't end at a fatal lexing error
+//! let tokens = Tokens::new("1 + 2 $").on_lex_error(logos_nom_bridge::LexErrorPolicy::Fail);
+//! let result: IResult<_, _> = expr.parse(tokens);
+//! assert!(matches!(result, Err(nom::Err::Failure(_))));
+//! ```
+
+use alloc::{boxed::Box, vec::Vec};
+
+use logos::Logos;
+use nom::{
+    error::{ErrorKind, ParseError},
+    IResult, Parser,
+};
+
+use crate::{TokenSource, Tokens};
+
+/// The associativity of a binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Assoc {
+    /// `a - b - c` is parsed as `(a - b) - c`.
+    Left,
+    /// `a ^ b ^ c` is parsed as `a ^ (b ^ c)`.
+    Right,
+}
+
+/// A parser for expressions consisting of operands and prefix, postfix and binary operators.
+///
+/// Operands are parsed with the `atom` parser. Operators are single tokens, registered with a
+/// precedence and a callback that builds the output. Operators with a higher precedence bind
+/// more tightly. See the [module documentation](self) for an example.
+pub struct ExprParser<T, O, P> {
+    atom: P,
+    prefix: Vec<(T, u32, Unary<O>)>,
+    postfix: Vec<(T, u32, Unary<O>)>,
+    binary: Vec<(T, u32, Assoc, Binary<O>)>,
+}
+
+type Unary<O> = Box<dyn Fn(O) -> O>;
+type Binary<O> = Box<dyn Fn(O, O) -> O>;
+
+impl<T, O, P> ExprParser<T, O, P> {
+    /// Creates an expression parser without operators that parses operands with `atom`.
+    pub fn new(atom: P) -> Self {
+        ExprParser {
+            atom,
+            prefix: Vec::new(),
+            postfix: Vec::new(),
+            binary: Vec::new(),
+        }
+    }
+
+    /// Registers a prefix operator.
+    pub fn prefix(mut self, token: T, precedence: u32, f: impl Fn(O) -> O + 'static) -> Self {
+        self.prefix.push((token, precedence, Box::new(f)));
+        self
+    }
+
+    /// Registers a postfix operator.
+    pub fn postfix(mut self, token: T, precedence: u32, f: impl Fn(O) -> O + 'static) -> Self {
+        self.postfix.push((token, precedence, Box::new(f)));
+        self
+    }
+
+    /// Registers a binary infix operator.
+    pub fn binary(
+        mut self,
+        token: T,
+        precedence: u32,
+        assoc: Assoc,
+        f: impl Fn(O, O) -> O + 'static,
+    ) -> Self {
+        self.binary.push((token, precedence, assoc, Box::new(f)));
+        self
+    }
+
+    /// Parses an expression whose operators all have a binding power of at least `min_bp`.
+    fn parse_bp<'i, E>(&mut self, input: Tokens<'i, T>, min_bp: u32) -> IResult<Tokens<'i, T>, O, E>
+    where
+        T: Logos<'i> + PartialEq + Clone,
+        T::Source: TokenSource,
+        T::Extras: Clone,
+        P: Parser<Tokens<'i, T>, O, E>,
+        E: ParseError<Tokens<'i, T>>,
+    {
+        let (mut input, mut lhs) = match self.find_prefix(&input) {
+            Some((i, rest)) => {
+                let bp = self.prefix[i].1 * 2;
+                let (rest, operand) = self.parse_bp(rest, bp)?;
+                (rest, (self.prefix[i].2)(operand))
+            }
+            None => self.atom.parse(input)?,
+        };
+
+        loop {
+            let Some((token, _)) = input.peek_spanned() else {
+                // an operator may follow in the next chunk
+                if input.is_streaming() {
+                    return Err(nom::Err::Incomplete(nom::Needed::Unknown));
+                }
+                if input.is_at_fatal_lex_error() {
+                    return Err(nom::Err::Failure(E::from_error_kind(input, ErrorKind::Eof)));
+                }
+                break;
+            };
+            if let Some((_, precedence, f)) = self.postfix.iter().find(|(t, ..)| *t == token) {
+                if precedence * 2 < min_bp {
+                    break;
+                }
+                lhs = f(lhs);
+                input = input.advance();
+                continue;
+            }
+            let Some(i) = self.binary.iter().position(|(t, ..)| *t == token) else {
+                break;
+            };
+            let (_, precedence, assoc, _) = &self.binary[i];
+            let (left_bp, right_bp) = match assoc {
+                Assoc::Left => (precedence * 2, precedence * 2 + 1),
+                Assoc::Right => (precedence * 2 + 1, precedence * 2),
+            };
+            if left_bp < min_bp {
+                break;
+            }
+            let (rest, rhs) = self.parse_bp(input.advance(), right_bp)?;
+            lhs = (self.binary[i].3)(lhs, rhs);
+            input = rest;
+        }
+        Ok((input, lhs))
+    }
+
+    /// Returns the index of the prefix operator at the start of `input`, and the input after it
+    fn find_prefix<'i>(&self, input: &Tokens<'i, T>) -> Option<(usize, Tokens<'i, T>)>
+    where
+        T: Logos<'i> + PartialEq + Clone,
+        T::Source: TokenSource,
+        T::Extras: Clone,
+    {
+        let (token, _) = input.peek_spanned()?;
+        let i = self.prefix.iter().position(|(t, ..)| *t == token)?;
+        Some((i, input.clone().advance()))
+    }
+}
+
+impl<'i, T, O, E, P> Parser<Tokens<'i, T>, O, E> for ExprParser<T, O, P>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+    E: ParseError<Tokens<'i, T>>,
+{
+    fn parse(&mut self, input: Tokens<'i, T>) -> IResult<Tokens<'i, T>, O, E> {
+        self.parse_bp(input, 0)
+    }
+}
@@ -9,6 +9,7 @@
 //! // First, create a `logos` lexer:
 //!
 //! #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+//! #[logos(skip r"[ \t\n\f]+")]
 //! enum Token {
 //!     #[token("+")]
 //!     Plus,
@@ -16,12 +17,8 @@
 //!     #[token("-")]
 //!     Minus,
 //!
-//!     #[regex(r"-?[0-9]+", |lex| lex.slice().parse())]
+//!     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
 //!     Number(i64),
-//!
-//!     #[error]
-//!     #[regex(r"[ \t\n\f]+", logos::skip)]
-//!     Error,
 //! }
 //!
 //! // Then, write a nom parser that accepts a `Tokens<'_, Token>` as input:
@@ -94,8 +91,6 @@
 //! ```
 //! # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
 //! # enum Token {
-//! #     #[error]
-//! #     Error,
 //! # }
 //! #
 //! logos_nom_bridge::token_parser!(token: Token);
@@ -109,12 +104,10 @@
 //! #
 //! #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
 //! enum Token {
-//!     #[regex(r"-?[0-9]+", |lex| lex.slice().parse())]
+//!     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
 //!     Number(i64),
 //!
 //!     // etc.
-//! #   #[error]
-//! #   Error,
 //! }
 //!
 //! logos_nom_bridge::data_variant_parser! {
@@ -122,13 +115,70 @@
 //!     pattern = Token::Number(n) => Op::Number(n);
 //! }
 //! ```
+//!
+//! ## Features
+//!
+//! The crate supports `no_std` environments with an allocator when the default `std` feature
+//! is disabled. The `futures-io` feature adds `StreamBuffer::fill` for reading from an
+//! `AsyncRead`. The `nom-supreme` feature adds support for nom-supreme's `ErrorTree` to the
+//! [`error`] module. The `miette`, `ariadne` and `codespan-reporting` features add conversions
+//! to the types of these crates to the [`diagnostics`] module. The `serde` feature adds
+//! `Tokens::snapshot` for serializing the remaining tokens. The `tracing` feature emits `trace!`
+//! events with the target `logos_nom_bridge` when a token is consumed and when a parser
+//! generated by [`token_parser`] or [`data_variant_parser`] fails.
+
+#![no_std]
 
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod buffered;
+mod chunked;
+pub mod combinators;
+pub mod diagnostics;
+pub mod error;
+pub mod expr;
+pub mod line_index;
 mod macros;
+pub mod recovery;
+#[cfg(feature = "serde")]
+mod snapshot;
+mod source;
+pub mod span;
+pub mod stats;
+mod stream;
+pub mod trace;
+
+pub use buffered::{BufferedTokens, Elements, TokenSlice};
+pub use chunked::{ChunkedIter, ChunkedTokens};
+#[cfg(feature = "serde")]
+pub use snapshot::Snapshot;
+pub use source::TokenSource;
+pub use stream::StreamBuffer;
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing as __tracing;
 
-use core::fmt;
+use alloc::{rc::Rc, vec, vec::Vec};
+use core::{
+    cell::{OnceCell, RefCell},
+    fmt,
+    hash::{Hash, Hasher},
+    iter,
+    mem::{self, Discriminant},
+    ops::{Range, RangeFrom, RangeFull, RangeTo},
+    str::{FromStr, Utf8Error},
+};
 
-use logos::{Lexer, Logos, Span, SpannedIter};
-use nom::{InputIter, InputLength, InputTake};
+use logos::{Lexer, Logos, Source, Span};
+use nom::{
+    error::{ErrorKind, ParseError},
+    Compare, CompareResult, FindToken, IResult, InputIter, InputLength, InputTake,
+    InputTakeAtPosition, Offset, ParseTo, Slice,
+};
+
+use crate::{line_index::LineIndex, stats::ParseStats, trace::Tracer};
 
 /// A [`logos::Lexer`] wrapper than can be used as an input for
 /// [nom](https://docs.rs/nom/7.0.0/nom/index.html).
@@ -139,75 +189,2212 @@ where
     T: Logos<'i>,
 {
     lexer: Lexer<'i, T>,
+    /// The next token, lexed lazily by `peek`
+    peeked: OnceCell<Rc<Peeked<'i, T>>>,
+    hooks: Rc<Hooks<'i, T>>,
+    inserted: Option<Rc<Inserted<T>>>,
+    /// The lexing errors recorded with [`LexErrorPolicy::Collect`], most recent first
+    lex_errors: Option<Rc<LexErrors<T::Error>>>,
+    /// The number of tokens consumed since the input was created
+    consumed: usize,
+    /// The span of the last consumed token
+    span: Span,
+}
+
+/// Callbacks that are applied to tokens as they are lexed and consumed
+struct Hooks<'i, T: Logos<'i>> {
+    trivia: Option<TriviaSink<'i, SourceSlice<'i, T>>>,
+    /// Returns `true` for tokens that should be skipped
+    filter: Option<Filter<'i, T>>,
+    /// Replaces tokens before they are filtered
+    map: Option<Mapper<'i, T>>,
+    layout: Option<Rc<Layout<T>>>,
+    terminator: Option<Rc<Terminator<'i, T>>>,
+    /// The length of the source received so far, if more input may follow
+    chunk_len: Option<usize>,
+    lex_error_policy: LexErrorPolicy,
+    /// The unit of [`InputLength`]
+    unit: LengthUnit,
+    /// The number of tokens shown by the `Debug` impl
+    debug_preview: usize,
+    tracer: Option<Tracer>,
+    stats: Option<ParseStats>,
+    /// The line index of the source, built lazily by `line_index` and shared by all copies
+    line_index: Rc<OnceCell<LineIndex<'i>>>,
+}
+
+type Filter<'i, T> = Rc<dyn Fn(&T) -> bool + 'i>;
+type Mapper<'i, T> = Rc<dyn Fn(T, &Lexer<'i, T>) -> T + 'i>;
+
+impl<'i, T: Logos<'i>> Clone for Hooks<'i, T> {
+    fn clone(&self) -> Self {
+        Hooks {
+            trivia: self.trivia.clone(),
+            filter: self.filter.clone(),
+            map: self.map.clone(),
+            layout: self.layout.clone(),
+            terminator: self.terminator.clone(),
+            chunk_len: self.chunk_len,
+            lex_error_policy: self.lex_error_policy,
+            unit: self.unit,
+            debug_preview: self.debug_preview,
+            tracer: self.tracer.clone(),
+            stats: self.stats.clone(),
+            line_index: self.line_index.clone(),
+        }
+    }
+}
+
+impl<'i, T: Logos<'i>> Default for Hooks<'i, T> {
+    fn default() -> Self {
+        Hooks {
+            trivia: None,
+            filter: None,
+            map: None,
+            layout: None,
+            terminator: None,
+            chunk_len: None,
+            lex_error_policy: LexErrorPolicy::Stop,
+            unit: LengthUnit::Bytes,
+            debug_preview: 5,
+            tracer: None,
+            stats: None,
+            line_index: Rc::default(),
+        }
+    }
+}
+
+/// A token lexed by `peek`, kept so that consuming it doesn't lex it again
+struct Peeked<'i, T: Logos<'i>> {
+    token: Option<(T, Span)>,
+    /// The input after the token
+    rest: Tokens<'i, T>,
+}
+
+/// A lexing error recorded with [`LexErrorPolicy::Collect`]. The errors form a linked list, so
+/// recording one doesn't copy the errors that copies of the input share.
+struct LexErrors<E> {
+    error: (E, Span),
+    /// The number of errors in the list, including this one
+    len: usize,
+    prev: Option<Rc<LexErrors<E>>>,
 }
 
+impl<E> LexErrors<E> {
+    /// Adds an error to the front of `list`
+    fn push(list: &mut Option<Rc<Self>>, error: (E, Span)) {
+        let len = Self::len(list) + 1;
+        let prev = list.take();
+        *list = Some(Rc::new(LexErrors { error, len, prev }));
+    }
+
+    /// Returns the number of errors in `list`
+    fn len(list: &Option<Rc<Self>>) -> usize {
+        list.as_ref().map_or(0, |node| node.len)
+    }
+
+    /// Adds the `n` most recent errors of `from` to `list`, converting them with [`Into`]
+    fn append<F>(list: &mut Option<Rc<Self>>, from: &Option<Rc<LexErrors<F>>>, n: usize)
+    where
+        F: Clone + Into<E>,
+    {
+        let errors: Vec<_> = LexErrors::iter(from).take(n).collect();
+        for (e, span) in errors.into_iter().rev() {
+            Self::push(list, (e.clone().into(), span.clone()));
+        }
+    }
+
+    /// Returns the errors, most recent first
+    fn iter(list: &Option<Rc<Self>>) -> impl Iterator<Item = &(E, Span)> {
+        iter::successors(list.as_deref(), |node| node.prev.as_deref()).map(|node| &node.error)
+    }
+}
+
+impl<E> Drop for LexErrors<E> {
+    fn drop(&mut self) {
+        // drop long lists iteratively to avoid overflowing the stack
+        let mut prev = self.prev.take();
+        while let Some(node) = prev {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => prev = node.prev.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// The synthetic tokens inserted by [`Tokens::with_layout`]
+struct Layout<T> {
+    newline: T,
+    indent: T,
+    dedent: T,
+}
+
+/// The synthetic token inserted by [`Tokens::with_terminators`]
+struct Terminator<'i, T> {
+    token: T,
+    ends_statement: Filter<'i, T>,
+}
+
+/// The state of the synthetic tokens inserted into the stream
+#[derive(Clone)]
+struct Inserted<T> {
+    /// The position for which the tokens were computed
+    at: usize,
+    /// The tokens that weren't consumed yet, in reverse order
+    pending: Vec<(T, Span)>,
+    /// The indentation of the enclosing blocks, starting with 0
+    indents: Vec<usize>,
+    /// Whether the last token from the source can end a statement
+    ends_statement: bool,
+}
+
+impl<T> Inserted<T> {
+    fn indent(&self) -> usize {
+        self.indents.last().copied().unwrap_or(0)
+    }
+}
+
+/// The unit in which the length of [`Tokens`] is measured by [`InputLength`]. Offsets and counts
+/// used by [`InputIter`] and [`InputTake`] are measured in the same unit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LengthUnit {
+    /// The number of remaining bytes in the source. This is the default.
+    #[default]
+    Bytes,
+    /// The number of remaining tokens. Counting them requires lexing the rest of the input,
+    /// which is expensive for large inputs.
+    Tokens,
+}
+
+/// What [`Tokens`] does when the lexer can't recognize the source text. See
+/// [`Tokens::on_lex_error`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LexErrorPolicy {
+    /// The tokens end before the error, so parsers fail with a recoverable error at that
+    /// position. The error is available from [`Tokens::lex_error`]. This is the default.
+    #[default]
+    Stop,
+    /// The erroneous text is skipped like whitespace.
+    Skip,
+    /// Like [`LexErrorPolicy::Stop`], but the parsers generated by [`token_parser`] and
+    /// [`data_variant_parser`], as well as [`end`](combinators::end), return
+    /// [`nom::Err::Failure`] at the error, so that alternatives aren't tried.
+    Fail,
+    /// The erroneous text is skipped, and the error is recorded. The recorded errors are
+    /// available from [`Tokens::collected_lex_errors`] and [`Tokens::lex_error_slices`].
+    Collect,
+}
+
+/// A position in the source of [`Tokens`], returned by [`Tokens::position`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SourcePosition {
+    /// The byte offset in the source.
+    pub byte: usize,
+    /// The number of tokens consumed before this position.
+    pub token_index: usize,
+    /// The 1-based line number.
+    pub line: usize,
+    /// The 1-based column number, counted in `char`s.
+    pub column: usize,
+}
+
+/// The type of slices of the source of `T`: `str` for string sources and `[u8]` for byte
+/// sources.
+pub type SourceSlice<'i, T> = <<T as Logos<'i>>::Source as Source>::Slice;
+
+/// A shared buffer that receives the source text skipped between consumed tokens, together
+/// with its span. See [`Tokens::with_trivia_sink`].
+pub type TriviaSink<'i, S = str> = Rc<RefCell<Vec<(&'i S, Span)>>>;
+
 impl<'i, T> Clone for Tokens<'i, T>
 where
     T: Logos<'i> + Clone,
     T::Extras: Clone,
 {
     fn clone(&self) -> Self {
+        if let Some(stats) = &self.hooks.stats {
+            stats.update(|s| s.clones += 1);
+        }
         Self {
             lexer: self.lexer.clone(),
+            peeked: self.peeked.clone(),
+            hooks: self.hooks.clone(),
+            inserted: self.inserted.clone(),
+            lex_errors: self.lex_errors.clone(),
+            consumed: self.consumed,
+            span: self.span.clone(),
         }
     }
 }
 
 impl<'i, T> Tokens<'i, T>
 where
-    T: Logos<'i, Source = str> + Clone,
-    T::Extras: Default + Clone,
+    T: Logos<'i>,
 {
-    pub fn new(input: &'i str) -> Self {
+    /// Creates a new `Tokens` whose lexer starts with the given extras, like
+    /// [`Lexer::with_extras`]. This doesn't require `T::Extras` to implement [`Default`].
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone)]
+    /// struct Interner(Vec<String>);
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(extras = Interner)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Token {
+    ///     #[regex(r"[a-z]+", |lex| {
+    ///         let word = lex.slice().to_string();
+    ///         let words = &mut lex.extras.0;
+    ///         words.iter().position(|w| *w == word).unwrap_or_else(|| {
+    ///             words.push(word);
+    ///             words.len() - 1
+    ///         })
+    ///     })]
+    ///     Word(usize),
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let interner = Interner(vec!["world".to_string()]);
+    /// let tokens = Tokens::<Token>::new_with_extras("hello world", interner);
+    ///
+    /// assert_eq!(tokens.peek(), Some((Token::Word(1), "hello")));
+    /// assert_eq!(tokens.advance().peek(), Some((Token::Word(0), "world")));
+    /// ```
+    pub fn new_with_extras(input: &'i T::Source, extras: T::Extras) -> Self {
+        Self::from_lexer(Lexer::with_extras(input, extras))
+    }
+
+    /// Wraps an existing lexer. Parsing continues at the lexer's current position, and the
+    /// lexer's extras are kept.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos::Logos;
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let mut lexer = Token::lexer("1 + 2");
+    /// lexer.next();
+    ///
+    /// let tokens = Tokens::from_lexer(lexer);
+    /// assert_eq!(tokens.peek(), Some((Token::Plus, "+")));
+    /// ```
+    pub fn from_lexer(lexer: Lexer<'i, T>) -> Self {
+        let span = lexer.span();
         Tokens {
-            lexer: Lexer::new(input),
+            lexer,
+            peeked: OnceCell::new(),
+            hooks: Rc::default(),
+            inserted: None,
+            lex_errors: None,
+            consumed: 0,
+            span,
+        }
+    }
+
+    /// Returns the wrapped lexer, positioned after the last consumed token.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 2").advance();
+    /// let mut lexer = tokens.into_inner();
+    ///
+    /// assert_eq!(lexer.next(), Some(Ok(Token::Plus)));
+    /// assert_eq!(lexer.next(), Some(Ok(Token::Number(2))));
+    /// ```
+    pub fn into_inner(self) -> Lexer<'i, T> {
+        self.lexer
+    }
+
+    /// Returns a reference to the wrapped lexer.
+    pub fn as_lexer(&self) -> &Lexer<'i, T> {
+        &self.lexer
+    }
+
+    /// Switches to a different token type, continuing at the current position. This uses
+    /// [`Lexer::morph`], so the extras are converted with [`Into`].
+    ///
+    /// The [length unit](Tokens::with_length_unit), the [trivia sink](Tokens::with_trivia_sink),
+    /// the [tracer](Tokens::with_tracer), the [stats collector](Tokens::with_stats), the
+    /// [streaming](Tokens::streaming) state and the [lexing error policy](Tokens::on_lex_error)
+    /// are kept, and the [collected lexing errors](Tokens::collected_lex_errors) are converted
+    /// with [`Into`]. Other configuration depends on the token type and is discarded. To switch the token type only for a sub-parser, use
+    /// [`with_mode`](combinators::with_mode).
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Outer {
+    ///     #[regex("[a-z]+")]
+    ///     Word,
+    ///
+    ///     #[token("\"")]
+    ///     Quote,
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// enum Inner {
+    ///     #[regex(r#"[^"\\]+"#)]
+    ///     Text,
+    ///
+    ///     #[regex(r"\\.")]
+    ///     Escape,
+    ///
+    ///     #[token("\"")]
+    ///     Quote,
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Outer>::new(r#"say "hi \" there""#).advance().advance();
+    ///
+    /// let inner: Vec<_> = tokens.morph::<Inner>().iter().map(|(t, _, _)| t).collect();
+    /// assert_eq!(inner, [Inner::Text, Inner::Escape, Inner::Text, Inner::Quote]);
+    /// ```
+    pub fn morph<U>(self) -> Tokens<'i, U>
+    where
+        U: Logos<'i, Source = T::Source>,
+        T::Extras: Into<U::Extras>,
+        T::Error: Into<U::Error>,
+    {
+        let mut tokens = Tokens::from_lexer(self.lexer.morph());
+        tokens.consumed = self.consumed;
+        tokens.span = self.span;
+        let n = LexErrors::len(&self.lex_errors);
+        LexErrors::append(&mut tokens.lex_errors, &self.lex_errors, n);
+        let hooks = tokens.hooks_mut();
+        hooks.chunk_len = self.hooks.chunk_len;
+        hooks.unit = self.hooks.unit;
+        hooks.trivia = self.hooks.trivia.clone();
+        hooks.lex_error_policy = self.hooks.lex_error_policy;
+        hooks.line_index = self.hooks.line_index.clone();
+        hooks.tracer = self.hooks.tracer.clone();
+        hooks.stats = self.hooks.stats.clone();
+        tokens
+    }
+
+    /// Returns the source string, including the parts that were already consumed.
+    ///
+    /// When the input was split with [`InputTake`], the source of the first half ends where the
+    /// first half ends. Spans always refer to positions in the original source.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 2").advance().advance();
+    /// assert_eq!(tokens.source(), "1 + 2");
+    /// ```
+    pub fn source(&self) -> &'i T::Source {
+        self.lexer.source()
+    }
+
+    /// Returns the part of the source that hasn't been consumed yet.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 2").advance();
+    /// assert_eq!(tokens.remainder(), " + 2");
+    /// ```
+    pub fn remainder(&self) -> &'i SourceSlice<'i, T> {
+        self.lexer.remainder()
+    }
+
+    /// Returns the span of the last consumed token, or `0..0` if no token was consumed yet. Use
+    /// [`Tokens::peek_with_span`] to get the span of the next token.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("10 + 2");
+    /// assert_eq!(tokens.current_span(), 0..0);
+    /// assert_eq!(tokens.clone().advance().advance().current_span(), 3..4);
+    ///
+    /// // tokens skipped by `take_split` count as consumed
+    /// use nom::InputTake;
+    ///
+    /// let (rest, _) = tokens.take_split(4);
+    /// assert_eq!(rest.current_span(), 3..4);
+    /// ```
+    pub fn current_span(&self) -> Span {
+        self.span.clone()
+    }
+
+    /// Returns the extras of the lexer.
+    pub fn extras(&self) -> &T::Extras {
+        &self.lexer.extras
+    }
+
+    /// Returns the extras of the lexer mutably, so parsers can update the lexer's state.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(extras = usize)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Token {
+    ///     #[token("{", |lex| lex.extras += 1)]
+    ///     Open,
+    ///
+    ///     #[token("}", |lex| lex.extras -= 1)]
+    ///     Close,
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("{ { }").advance().advance();
+    /// assert_eq!(*tokens.extras(), 2);
+    ///
+    /// let mut tokens = tokens;
+    /// *tokens.extras_mut() = 10;
+    /// assert_eq!(*tokens.advance().extras(), 9);
+    /// ```
+    pub fn extras_mut(&mut self) -> &mut T::Extras {
+        // the peeked token may depend on the extras
+        self.peeked = OnceCell::new();
+        &mut self.lexer.extras
+    }
+
+    /// Creates a new `Tokens` with the same configuration as `self`
+    fn with_lexer(&self, lexer: Lexer<'i, T>) -> Self {
+        let mut hooks = self.hooks.clone();
+        if !core::ptr::eq(lexer.source(), self.lexer.source()) {
+            // a source cut off by `InputTake` needs its own line index
+            Rc::make_mut(&mut hooks).line_index = Rc::default();
+        }
+        Tokens {
+            lexer,
+            peeked: OnceCell::new(),
+            hooks,
+            inserted: self.inserted.clone(),
+            lex_errors: self.lex_errors.clone(),
+            consumed: self.consumed,
+            span: self.span.clone(),
+        }
+    }
+
+    /// Returns `true` if the input was created with [`Tokens::streaming`], so more input may
+    /// follow the end of the source.
+    pub fn is_streaming(&self) -> bool {
+        self.hooks.chunk_len.is_some()
+    }
+
+    /// Returns the part of the source at `span`
+    fn source_slice(&self, span: Span) -> &'i SourceSlice<'i, T> {
+        self.lexer
+            .source()
+            .slice(span)
+            .expect("span is within the source")
+    }
+
+    /// Returns the [lexing error policy](Tokens::on_lex_error)
+    pub fn lex_error_policy(&self) -> LexErrorPolicy {
+        self.hooks.lex_error_policy
+    }
+
+    /// Returns the lexing errors that were skipped with [`LexErrorPolicy::Collect`] before
+    /// the current position, in the order in which they appear in the source. See
+    /// [`Tokens::on_lex_error`].
+    pub fn collected_lex_errors(&self) -> Vec<(T::Error, Span)> {
+        let mut errors: Vec<_> = LexErrors::iter(&self.lex_errors).cloned().collect();
+        errors.reverse();
+        errors
+    }
+
+    /// Returns the spans and source text of the lexing errors that were skipped with
+    /// [`LexErrorPolicy::Collect`] before the current position.
+    ///
+    /// The errors are stored in the `Tokens` rather than in a shared buffer, so errors that were
+    /// skipped by a parser that later backtracked are not included.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::{combinators::end, LexErrorPolicy, Tokens};
+    /// use nom::{multi::separated_list1, sequence::terminated, IResult};
+    ///
+    /// logos_nom_bridge::token_parser!(token: Token);
+    ///
+    /// logos_nom_bridge::data_variant_parser! {
+    ///     fn number(input) -> Result<i64>;
+    ///     pattern = Token::Number(n) => n;
+    /// }
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 2 $ + 3 @").on_lex_error(LexErrorPolicy::Collect);
+    /// let result: IResult<_, _> = terminated(separated_list1(Token::Plus, number), end)(tokens);
+    /// let (rest, numbers) = result.unwrap();
+    ///
+    /// assert_eq!(numbers, [1, 2, 3]);
+    /// assert_eq!(rest.lex_error_slices(), [(6..7, "$"), (12..13, "@")]);
+    /// ```
+    pub fn lex_error_slices(&self) -> Vec<(Span, &'i SourceSlice<'i, T>)> {
+        let mut slices: Vec<_> = LexErrors::iter(&self.lex_errors)
+            .map(|(_, span)| (span.clone(), self.source_slice(span.clone())))
+            .collect();
+        slices.reverse();
+        slices
+    }
+
+    fn trivia(&self) -> Option<&TriviaSink<'i, SourceSlice<'i, T>>> {
+        self.hooks.trivia.as_ref()
+    }
+
+    /// Returns the hooks for modification, cloning them if they are shared
+    fn hooks_mut(&mut self) -> &mut Hooks<'i, T> {
+        Rc::make_mut(&mut self.hooks)
+    }
+
+    fn unit(&self) -> LengthUnit {
+        self.hooks.unit
+    }
+}
+
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    /// Returns a copy of `self` for looking ahead without consuming anything
+    fn lookahead(&self) -> Self {
+        self.with_lexer(self.lexer.clone())
+    }
+
+    /// Lexes the next token, applying the configured hooks and inserting synthetic tokens. All
+    /// tokens are lexed through this function.
+    fn lex_next(&mut self) -> Option<(T, Span)> {
+        if self.hooks.layout.is_none() && self.hooks.terminator.is_none() {
+            return self.lex_real();
+        }
+        let hooks = self.hooks.clone();
+        let pos = self.lexer.span().end;
+        if self.inserted.as_ref().is_none_or(|state| state.at != pos) {
+            self.insert_tokens(&hooks, pos);
+        }
+        if let Some(state) = &mut self.inserted {
+            if let Some(token) = Rc::make_mut(state).pending.pop() {
+                return Some(token);
+            }
+        }
+
+        let token = self.lex_real();
+        if let (Some(terminator), Some((t, _)), Some(state)) =
+            (&hooks.terminator, &token, &mut self.inserted)
+        {
+            Rc::make_mut(state).ends_statement = (terminator.ends_statement)(t);
+        }
+        token
+    }
+
+    /// Lexes the next token from the source, applying the configured hooks. Lexing errors
+    /// are handled according to the [`LexErrorPolicy`]; when the tokens stop at an error, the
+    /// error isn't consumed and `None` is returned.
+    fn lex_real(&mut self) -> Option<(T, Span)> {
+        loop {
+            match self.lex_item()? {
+                (Ok(token), span) => return Some((token, span)),
+                (Err(e), span) => match self.lex_error_policy() {
+                    LexErrorPolicy::Skip => {}
+                    LexErrorPolicy::Collect => LexErrors::push(&mut self.lex_errors, (e, span)),
+                    LexErrorPolicy::Stop | LexErrorPolicy::Fail => {
+                        self.lexer = self.lexer_at(span.start, self.lexer.source().len());
+                        return None;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Lexes the next item from the source, which is a token or a lexing error, applying the
+    /// configured hooks to tokens
+    fn lex_item(&mut self) -> Option<(Result<T, T::Error>, Span)> {
+        loop {
+            let backup = self.is_streaming().then(|| self.lexer.clone());
+            let item = self.lexer.next()?;
+            let chunk_len = self.hooks.chunk_len;
+            if chunk_len == Some(self.lexer.span().end) {
+                // The token might continue in the next chunk
+                self.lexer = backup?;
+                return None;
+            }
+            if let Some(stats) = &self.hooks.stats {
+                stats.update(|s| s.tokens_lexed += 1);
+            }
+            let mut token = match item {
+                Ok(token) => token,
+                Err(e) => return Some((Err(e), self.lexer.span())),
+            };
+            if let Some(map) = &self.hooks.map {
+                token = map(token, &self.lexer);
+            }
+            if self.hooks.filter.as_ref().is_some_and(|skip| skip(&token)) {
+                continue;
+            }
+            return Some((Ok(token), self.lexer.span()));
+        }
+    }
+
+    /// Computes the synthetic tokens to insert at `pos`, before the next token
+    fn insert_tokens(&mut self, hooks: &Hooks<'i, T>, pos: usize) {
+        let source = self.lexer.source().as_bytes();
+        let next = self.lookahead().lex_item().map(|(_, span)| span.start);
+        let gap = &source[pos..next.unwrap_or(source.len())];
+        let newline = gap
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(pos..pos, |i| pos + i..pos + i + 1);
+
+        let mut state = self.inserted.as_deref().cloned().unwrap_or(Inserted {
+            at: pos,
+            pending: Vec::new(),
+            indents: vec![0],
+            ends_statement: false,
+        });
+        let mut inserted = Vec::new();
+
+        if let Some(terminator) = &hooks.terminator {
+            if state.ends_statement && (next.is_none() || gap.contains(&b'\n')) {
+                inserted.push((terminator.token.clone(), newline.clone()));
+            }
+        }
+
+        if let Some(layout) = &hooks.layout {
+            match next {
+                None => {
+                    if pos != 0 {
+                        inserted.push((layout.newline.clone(), newline));
+                    }
+                    while state.indents.len() > 1 {
+                        state.indents.pop();
+                        inserted.push((layout.dedent.clone(), source.len()..source.len()));
+                    }
+                }
+                Some(start) if pos == 0 || gap.contains(&b'\n') => {
+                    if pos != 0 {
+                        inserted.push((layout.newline.clone(), newline));
+                    }
+                    let line_start = source[..start]
+                        .iter()
+                        .rposition(|&b| b == b'\n')
+                        .map_or(0, |i| i + 1);
+                    let indent = start - line_start;
+                    if indent > state.indent() {
+                        state.indents.push(indent);
+                        inserted.push((layout.indent.clone(), line_start..start));
+                    }
+                    while indent < state.indent() {
+                        state.indents.pop();
+                        inserted.push((layout.dedent.clone(), start..start));
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        inserted.reverse();
+        state.at = pos;
+        state.pending = inserted;
+        self.inserted = Some(Rc::new(state));
+    }
+
+    /// Converts a `count` in the input's [`LengthUnit`] to a byte offset relative to the
+    /// current position
+    fn byte_offset(&self, count: usize) -> usize {
+        match self.unit() {
+            LengthUnit::Bytes => count,
+            LengthUnit::Tokens => {
+                let start = self.lexer.span().end;
+                let mut tokens = self.lookahead();
+                for _ in 0..count {
+                    tokens.lex_next();
+                }
+                tokens.lexer.span().end - start
+            }
+        }
+    }
+
+    /// Returns the length of the input up to the end of its tokens, which is less than
+    /// `input_len()` if the tokens stop at a lexing error
+    fn tokens_len(&self) -> usize {
+        if self.unit() == LengthUnit::Tokens {
+            return self.input_len();
+        }
+        let mut rest = self.lookahead();
+        while rest.lex_next().is_some() {}
+        match rest.lex_error() {
+            Some((_, span)) => span.start - self.lexer.span().end,
+            None => self.input_len(),
+        }
+    }
+
+    /// Returns a lexer with the current extras at position `start` that stops at `end`
+    fn lexer_at(&self, start: usize, end: usize) -> Lexer<'i, T> {
+        let source = self.lexer.source().truncate(end);
+        let mut lexer = Lexer::with_extras(source, self.lexer.extras.clone());
+        lexer.bump(start);
+        lexer
+    }
+
+    /// Returns a stream over the tokens between the byte offsets `start` and `end`, where
+    /// `start` is `count` units after the current position. The skipped tokens are counted as
+    /// consumed.
+    fn skipped_to(&self, start: usize, end: usize, count: usize) -> Self {
+        let mut skipped = 0;
+        let mut last = self.span.clone();
+        for (_, span, _) in self.iter() {
+            let done = match self.unit() {
+                LengthUnit::Bytes => span.end > start,
+                LengthUnit::Tokens => skipped == count,
+            };
+            if done {
+                break;
+            }
+            skipped += 1;
+            last = span;
         }
+        let mut tokens = self.with_lexer(self.lexer_at(start, end));
+        tokens.consumed += skipped;
+        tokens.span = last;
+        tokens.record_consumed(skipped, self.lexer.span().end..start);
+        tokens
+    }
+
+    /// Updates the [`ParseStats`] after consuming `count` tokens covering `span`
+    fn record_consumed(&self, count: usize, span: Span) {
+        let Some(stats) = &self.hooks.stats else {
+            return;
+        };
+        stats.update(|s| {
+            s.tokens_consumed += count;
+            if span.start < s.furthest {
+                s.tokens_reconsumed += count;
+            }
+            s.furthest = s.furthest.max(span.end);
+        });
     }
+}
 
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i>,
+    T::Extras: Default,
+{
+    pub fn new(input: &'i T::Source) -> Self {
+        Self::new_with_extras(input, Default::default())
+    }
+}
+
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i, Source = str>,
+    T::Extras: Default,
+{
+    /// Creates a new `Tokens` from bytes, after checking that they are valid UTF-8.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # enum Token {
+    /// #     #[regex(r"[a-zä]+")]
+    /// #     Word,
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::try_from_bytes("bär".as_bytes()).unwrap();
+    /// assert_eq!(tokens.peek(), Some((Token::Word, "bär")));
+    ///
+    /// assert!(Tokens::<Token>::try_from_bytes(b"b\xFFr").is_err());
+    /// ```
+    pub fn try_from_bytes(input: &'i [u8]) -> Result<Self, Utf8Error> {
+        core::str::from_utf8(input).map(Self::new)
+    }
+}
+
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i, Source = str>,
+{
+    /// Returns the [`LineIndex`] of the source, for converting byte offsets to lines and
+    /// columns and back.
+    ///
+    /// The index is built the first time this is called, and shared by all copies of the input
+    /// with the same source, so it's usually built only once. The first half of an input split
+    /// with [`InputTake`] has a shorter [source](Tokens::source), so it gets its own index.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 +\n  2");
+    /// let (_, span, _) = tokens.clone().advance().advance().peek_with_span().unwrap();
+    ///
+    /// assert_eq!(tokens.line_index().line_col(span.start), (2, 3));
+    /// assert_eq!(tokens.line_index().offset(2, 3), Some(span.start));
+    /// ```
+    pub fn line_index(&self) -> &LineIndex<'i> {
+        self.hooks
+            .line_index
+            .get_or_init(|| LineIndex::new(self.lexer.source()))
+    }
+
+    /// Returns the current position, which is after the last consumed token. Use
+    /// [`Tokens::peek_with_span`] to get the position of the next token instead.
+    ///
+    /// The token index counts all tokens consumed since the input was created, including
+    /// tokens skipped with [`InputTake`] or [`Slice`]. The line and column are computed with
+    /// the [line index](Tokens::line_index).
+    ///
+    /// Note that this method shadows [`InputIter::position`], which must be called as
+    /// `InputIter::position(&tokens, predicate)`.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::{SourcePosition, Tokens};
+    ///
+    /// let tokens = Tokens::<Token>::new("1 +\n  22 + 3").advance_n(3).0;
+    ///
+    /// assert_eq!(
+    ///     tokens.position(),
+    ///     SourcePosition { byte: 8, token_index: 3, line: 2, column: 5 },
+    /// );
+    /// ```
+    pub fn position(&self) -> SourcePosition {
+        let byte = self.lexer.span().end;
+        let (line, column) = self.line_index().line_col(byte);
+        SourcePosition {
+            byte,
+            token_index: self.consumed,
+            line,
+            column,
+        }
+    }
+}
+
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    /// Returns the number of bytes of the source that haven't been consumed yet.
     pub fn len(&self) -> usize {
         self.lexer.source().len() - self.lexer.span().end
     }
 
+    /// Returns `true` if all tokens were consumed. This includes the synthetic tokens that
+    /// [`Tokens::with_layout`] and [`Tokens::with_terminators`] insert at the end of the
+    /// source, so the input may not be empty when [`Tokens::len`] is 0.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token(":")]
+    /// #     Colon,
+    /// #
+    /// #     #[regex("[a-z]+")]
+    /// #     Word,
+    /// #
+    /// #     Newline,
+    /// #     Indent,
+    /// #     Dedent,
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    /// use Token::*;
+    ///
+    /// let tokens = Tokens::new("if a:\n    b").with_layout(Newline, Indent, Dedent);
+    ///
+    /// let (rest, _) = tokens.advance_n(6);
+    /// assert_eq!(rest.len(), 0);
+    /// assert_eq!(rest.peek(), Some((Newline, "")));
+    /// assert!(!rest.is_empty());
+    ///
+    /// let (rest, _) = rest.advance_n(2);
+    /// assert!(rest.is_empty());
+    /// ```
     pub fn is_empty(&self) -> bool {
-        self.len() == 0
+        self.len() == 0 && self.peek_spanned().is_none()
+    }
+
+    /// Returns the number of remaining tokens. This lexes the rest of the input.
+    pub fn token_count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Lexes the remaining tokens into a `Vec`, without consuming them.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("10 + 3").advance();
+    ///
+    /// assert_eq!(tokens.to_vec(), [(Token::Plus, 3..4), (Token::Number(3), 5..6)]);
+    /// assert_eq!(tokens.peek(), Some((Token::Plus, "+")));
+    /// ```
+    pub fn to_vec(&self) -> Vec<(T, Span)> {
+        self.iter().map(|(t, span, _)| (t, span)).collect()
+    }
+
+    /// Sets the unit in which [`InputLength::input_len`] measures the input. By default, the
+    /// length is measured in bytes.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::{LengthUnit, Tokens};
+    /// use nom::InputLength;
+    ///
+    /// let tokens = Tokens::<Token>::new("10 + 3");
+    /// assert_eq!(tokens.input_len(), 6);
+    /// assert_eq!(tokens.token_count(), 3);
+    ///
+    /// let tokens = tokens.with_length_unit(LengthUnit::Tokens);
+    /// assert_eq!(tokens.input_len(), 3);
+    /// ```
+    pub fn with_length_unit(mut self, unit: LengthUnit) -> Self {
+        self.hooks_mut().unit = unit;
+        self
+    }
+
+    /// Sets how many of the upcoming tokens the [`Debug`](fmt::Debug) impl shows. The default
+    /// is 5.
+    pub fn with_debug_preview(mut self, len: usize) -> Self {
+        self.hooks_mut().debug_preview = len;
+        self
+    }
+
+    pub fn peek(&self) -> Option<(T, &'i SourceSlice<'i, T>)> {
+        self.peek_spanned()
+            .map(|(t, span)| (t, self.source_slice(span)))
+    }
+
+    /// Returns an iterator over the remaining tokens, without consuming them.
+    pub fn iter(&self) -> Iter<'i, T> {
+        Iter {
+            tokens: self.lookahead(),
+        }
+    }
+
+    /// Returns the token `n` positions ahead without consuming anything. `peek_nth(0)` is the
+    /// same as [`Tokens::peek`].
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("=")]
+    /// #     Eq,
+    /// #
+    /// #     #[token("(")]
+    /// #     LParen,
+    /// #
+    /// #     #[regex("[a-z]+")]
+    /// #     Ident,
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("f (x)");
+    /// assert_eq!(tokens.peek_nth(2), Some((Token::Ident, "x")));
+    /// assert_eq!(tokens.peek_nth(4), None);
+    ///
+    /// let is_call = matches!(tokens.peek2(), Some((Token::LParen, _)));
+    /// assert!(is_call);
+    /// ```
+    pub fn peek_nth(&self, n: usize) -> Option<(T, &'i SourceSlice<'i, T>)> {
+        if n == 0 {
+            return self.peek();
+        }
+        self.iter().nth(n).map(|(t, _, s)| (t, s))
+    }
+
+    /// Returns the token after the next one, without consuming anything.
+    pub fn peek2(&self) -> Option<(T, &'i SourceSlice<'i, T>)> {
+        self.peek_nth(1)
+    }
+
+    /// Returns the next token together with its span and source slice, without consuming it.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("10 + 2").advance();
+    /// assert_eq!(tokens.peek_with_span(), Some((Token::Plus, 3..4, "+")));
+    /// ```
+    pub fn peek_with_span(&self) -> Option<(T, Span, &'i SourceSlice<'i, T>)> {
+        self.peek_spanned().map(|(t, span)| {
+            let s = self.source_slice(span.clone());
+            (t, span, s)
+        })
+    }
+
+    /// Returns the lexing error at the current position, if any.
+    ///
+    /// Source text that the lexer can't recognize isn't turned into a token; instead, the
+    /// tokens end before it, so parsers fail at that position. This method can then be used to
+    /// find out why the input ended. With [`LexErrorPolicy::Skip`] or
+    /// [`LexErrorPolicy::Collect`], the tokens don't stop at errors, so this returns `None`.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    /// use nom::{multi::many0, IResult};
+    ///
+    /// logos_nom_bridge::token_parser!(token: Token);
+    ///
+    /// let result: IResult<_, _> = many0(Token::Plus)(Tokens::<Token>::new("+ + ? +"));
+    /// let (rest, plus) = result.unwrap();
+    /// assert_eq!(plus.len(), 2);
+    /// assert_eq!(rest.peek(), None);
+    /// assert_eq!(rest.lex_error(), Some(((), 4..5)));
+    /// ```
+    pub fn lex_error(&self) -> Option<(T::Error, Span)> {
+        if let LexErrorPolicy::Skip | LexErrorPolicy::Collect = self.lex_error_policy() {
+            return None;
+        }
+        match self.lookahead().lex_item()? {
+            (Err(e), span) => Some((e, span)),
+            (Ok(_), _) => None,
+        }
+    }
+
+    /// Returns `true` if the tokens stop at a lexing error that should be reported as a
+    /// [`nom::Err::Failure`], because the policy is [`LexErrorPolicy::Fail`].
+    pub fn is_at_fatal_lex_error(&self) -> bool {
+        self.lex_error_policy() == LexErrorPolicy::Fail && self.lex_error().is_some()
+    }
+
+    pub(crate) fn peek_spanned(&self) -> Option<(T, Span)> {
+        let peeked = self.peeked.get_or_init(|| {
+            let mut rest = self.lookahead();
+            let token = rest.lex_next();
+            Rc::new(Peeked { token, rest })
+        });
+        peeked.token.clone()
+    }
+
+    pub fn advance(self) -> Self {
+        self.next_token().0
+    }
+
+    /// Consumes the next token and returns it together with its span and source slice.
+    ///
+    /// A token that was already lexed by [`Tokens::peek`] isn't lexed again.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let (tokens, token) = Tokens::<Token>::new("1 +").next_token();
+    /// assert_eq!(token, Some((Token::Number(1), 0..1, "1")));
+    ///
+    /// let (tokens, token) = tokens.next_token();
+    /// assert_eq!(token, Some((Token::Plus, 2..3, "+")));
+    ///
+    /// let (_, token) = tokens.next_token();
+    /// assert_eq!(token, None);
+    ///
+    /// // peeking and then consuming a token lexes it once
+    /// use logos_nom_bridge::stats::ParseStats;
+    ///
+    /// let stats = ParseStats::new();
+    /// let tokens = Tokens::<Token>::new("1 +").with_stats(stats.clone());
+    /// assert_eq!(tokens.peek(), Some((Token::Number(1), "1")));
+    /// assert_eq!(tokens.advance().peek(), Some((Token::Plus, "+")));
+    /// assert_eq!(stats.get().tokens_lexed, 2);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn next_token(mut self) -> (Self, Option<(T, Span, &'i SourceSlice<'i, T>)>) {
+        let end = self.lexer.span().end;
+        let token = match self.peeked.take() {
+            Some(peeked) => {
+                self.lexer = peeked.rest.lexer.clone();
+                self.hooks = peeked.rest.hooks.clone();
+                self.inserted = peeked.rest.inserted.clone();
+                self.lex_errors = peeked.rest.lex_errors.clone();
+                peeked.token.clone()
+            }
+            None => self.lex_next(),
+        };
+        let token = token.map(|(t, span)| {
+            let s = self.source_slice(span.clone());
+            (t, span, s)
+        });
+        if let Some((_, span, _)) = &token {
+            self.consumed += 1;
+            self.span = span.clone();
+            self.record_consumed(1, span.clone());
+        }
+        #[cfg(feature = "tracing")]
+        if let Some((t, span, _)) = &token {
+            let slice = &self.lexer.source().as_bytes()[span.clone()];
+            tracing::trace!(
+                target: "logos_nom_bridge",
+                kind = ?mem::discriminant(t),
+                slice = %std::string::String::from_utf8_lossy(slice),
+                ?span,
+                "consumed token",
+            );
+        }
+
+        if let (Some((_, span, _)), Some(sink)) = (&token, self.trivia()) {
+            let skipped = end..span.start;
+            // synthetic tokens don't advance the lexer
+            if !skipped.is_empty() && self.lexer.span().end != end {
+                let slice = self.source_slice(skipped.clone());
+                sink.borrow_mut().push((slice, skipped));
+            }
+        }
+        (self, token)
+    }
+
+    /// Consumes up to `n` tokens, returning the advanced input and the consumed tokens with
+    /// their spans.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let (tokens, skipped) = Tokens::<Token>::new("1 + 2").advance_n(2);
+    ///
+    /// assert_eq!(skipped, [(Token::Number(1), 0..1), (Token::Plus, 2..3)]);
+    /// assert_eq!(tokens.peek(), Some((Token::Number(2), "2")));
+    /// ```
+    pub fn advance_n(mut self, n: usize) -> (Self, Vec<(T, Span)>) {
+        let mut skipped = Vec::with_capacity(n);
+        for _ in 0..n {
+            let (rest, token) = self.next_token();
+            self = rest;
+            match token {
+                Some((t, span, _)) => skipped.push((t, span)),
+                None => break,
+            }
+        }
+        (self, skipped)
+    }
+
+    /// Consumes tokens as long as `predicate` returns `true`, returning the advanced input and
+    /// the number of consumed tokens.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[token(";")]
+    /// #     Semicolon,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 2; 3");
+    /// let (tokens, skipped) = tokens.skip_while(|t| *t != Token::Semicolon);
+    ///
+    /// assert_eq!(skipped, 3);
+    /// assert_eq!(tokens.peek(), Some((Token::Semicolon, ";")));
+    /// ```
+    pub fn skip_while<P>(mut self, mut predicate: P) -> (Self, usize)
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let mut count = 0;
+        while let Some((token, _)) = self.peek_spanned() {
+            if !predicate(&token) {
+                break;
+            }
+            self = self.advance();
+            count += 1;
+        }
+        (self, count)
+    }
+
+    /// Consumes the next token if its enum variant is one of `kinds`, ignoring any data it
+    /// contains. Otherwise, the unchanged input is returned as the error.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Token {
+    ///     #[token("+")]
+    ///     Plus,
+    ///
+    ///     #[regex(r"[0-9]+", |lex| lex.slice().parse().ok())]
+    ///     Number(i64),
+    ///
+    ///     #[regex(r"[a-z]+", |lex| lex.slice().to_string())]
+    ///     Ident(String),
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    /// use std::mem::discriminant;
+    ///
+    /// let kinds = [
+    ///     discriminant(&Token::Number(0)),
+    ///     discriminant(&Token::Ident(String::new())),
+    /// ];
+    /// let tokens = Tokens::new("42 x +");
+    ///
+    /// let (number, tokens) = tokens.expect_any_kind(&kinds).unwrap();
+    /// assert_eq!(number, (Token::Number(42), "42"));
+    ///
+    /// let (ident, tokens) = tokens.expect_any_kind(&kinds).unwrap();
+    /// assert_eq!(ident, (Token::Ident("x".to_string()), "x"));
+    ///
+    /// let tokens = tokens.expect_any_kind(&kinds).unwrap_err();
+    /// assert_eq!(tokens.peek(), Some((Token::Plus, "+")));
+    /// ```
+    pub fn expect_any_kind(
+        self,
+        kinds: &[Discriminant<T>],
+    ) -> Result<((T, &'i SourceSlice<'i, T>), Self), Self> {
+        match self.peek() {
+            Some((token, s)) if kinds.contains(&mem::discriminant(&token)) => {
+                Ok(((token, s), self.advance()))
+            }
+            _ => Err(self),
+        }
+    }
+
+    /// Records the calls of parsers wrapped with [`trace`](trace::trace) in `tracer`. The tracer
+    /// is shared by all clones of this input. See the [`trace`] module for an example.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.hooks_mut().tracer = Some(tracer);
+        self
+    }
+
+    /// Counts the tokens lexed and consumed, the clones and the furthest position reached in
+    /// `stats`. The collector is shared by all clones of this input. See [`ParseStats`] for an
+    /// example.
+    pub fn with_stats(mut self, stats: ParseStats) -> Self {
+        self.hooks_mut().stats = Some(stats);
+        self
+    }
+
+    /// Records the source text skipped before each consumed token (e.g. whitespace and comments
+    /// marked with `logos::skip`) in `sink`.
+    ///
+    /// The sink is shared by all clones of this input, so text may be recorded more than once
+    /// if a parser backtracks. Trivia after the last token is not recorded.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// #[logos(skip r"/\*[^*]*\*/")]
+    /// enum Token {
+    ///     #[token("+")]
+    ///     Plus,
+    ///
+    ///     #[regex(r"[0-9]+")]
+    ///     Number,
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    /// use std::{cell::RefCell, rc::Rc};
+    ///
+    /// let sink = Rc::new(RefCell::new(Vec::new()));
+    /// let tokens = Tokens::<Token>::new("1 /* one */+2").with_trivia_sink(sink.clone());
+    /// let tokens = tokens.advance().advance();
+    ///
+    /// assert_eq!(tokens.peek(), Some((Token::Number, "2")));
+    /// assert_eq!(*sink.borrow(), [(" /* one */", 1..11)]);
+    /// ```
+    pub fn with_trivia_sink(mut self, sink: TriviaSink<'i, SourceSlice<'i, T>>) -> Self {
+        self.hooks_mut().trivia = Some(sink);
+        self
+    }
+
+    /// Skips all tokens for which `skip` returns `true`, as if they were marked with
+    /// `logos::skip`.
+    ///
+    /// This is useful when the lexer emits trivia tokens such as comments because another tool
+    /// needs them. Skipped tokens are invisible to [`Tokens::peek`], [`Tokens::advance`], the
+    /// nom traits and all combinators, and they're recorded by the
+    /// [trivia sink](Tokens::with_trivia_sink) like other skipped text.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// enum Token {
+    ///     #[token("+")]
+    ///     Plus,
+    ///
+    ///     #[regex(r"[0-9]+")]
+    ///     Number,
+    ///
+    ///     #[regex(r"[ \t\n\f]+")]
+    ///     Whitespace,
+    ///
+    ///     #[regex(r"/\*[^*]*\*/")]
+    ///     Comment,
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 /* one */ + 2")
+    ///     .with_trivia_filter(|t| matches!(t, Token::Whitespace | Token::Comment));
+    ///
+    /// let kinds: Vec<_> = tokens.iter().map(|(t, _, _)| t).collect();
+    /// assert_eq!(kinds, [Token::Number, Token::Plus, Token::Number]);
+    /// ```
+    pub fn with_trivia_filter(mut self, skip: impl Fn(&T) -> bool + 'i) -> Self {
+        self.peeked = OnceCell::new();
+        self.hooks_mut().filter = Some(Rc::new(skip));
+        self
+    }
+
+    /// Replaces every token with the result of `f` as it is lexed. `f` receives the token and
+    /// the lexer, which provides the token's slice, span and the extras.
+    ///
+    /// This can be used to rewrite contextual keywords depending on the parse state, which can
+    /// be stored in the extras and changed with [`Tokens::extras_mut`]. If this is called more
+    /// than once, the functions are applied in order. Mapping happens before the
+    /// [trivia filter](Tokens::with_trivia_filter) is applied.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(extras = bool)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Token {
+    ///     #[regex("[a-z]+")]
+    ///     Ident,
+    ///
+    ///     KwAsync,
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let mut tokens = Tokens::<Token>::new("async fn").map_tokens(|t, lex| match t {
+    ///     Token::Ident if lex.extras && lex.slice() == "async" => Token::KwAsync,
+    ///     t => t,
+    /// });
+    /// assert_eq!(tokens.peek(), Some((Token::Ident, "async")));
+    ///
+    /// *tokens.extras_mut() = true;
+    /// assert_eq!(tokens.peek(), Some((Token::KwAsync, "async")));
+    /// ```
+    pub fn map_tokens(mut self, f: impl Fn(T, &Lexer<'i, T>) -> T + 'i) -> Self
+    where
+        T: 'i,
+    {
+        self.peeked = OnceCell::new();
+        let hooks = self.hooks_mut();
+        hooks.map = Some(match hooks.map.take() {
+            Some(prev) => Rc::new(move |t, lexer| f(prev(t, lexer), lexer)),
+            None => Rc::new(f),
+        });
+        self
+    }
+
+    /// Inserts synthetic `newline`, `indent` and `dedent` tokens based on the indentation of
+    /// each line, like Python's tokenizer.
+    ///
+    /// Before the first token of each line except the first one, a `newline` token is inserted.
+    /// If the line is indented more than the enclosing block, an `indent` token follows;
+    /// if it's indented less, a `dedent` token follows for every block that is closed. At the
+    /// end of the input, a `newline` and the remaining `dedent` tokens are inserted.
+    /// Indentation is measured in bytes, so tabs and spaces count the same.
+    ///
+    /// The synthetic tokens don't exist in the source: a `newline` token spans the line break,
+    /// an `indent` token spans the indentation, and a `dedent` token is empty. Because they
+    /// don't advance the lexer, this also sets the [`LengthUnit`] to [`LengthUnit::Tokens`], so
+    /// combinators like [`nom::multi::many0`] see progress when they are consumed.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Token {
+    ///     #[token(":")]
+    ///     Colon,
+    ///
+    ///     #[regex("[a-z]+")]
+    ///     Word,
+    ///
+    ///     Newline,
+    ///     Indent,
+    ///     Dedent,
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    /// use Token::*;
+    ///
+    /// let tokens = Tokens::new("if a:\n    b\n    c\nd").with_layout(Newline, Indent, Dedent);
+    ///
+    /// let kinds: Vec<_> = tokens.iter().map(|(t, _, _)| t).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     [
+    ///         Word, Word, Colon, Newline, Indent, Word, Newline, Word, Newline, Dedent, Word,
+    ///         Newline,
+    ///     ],
+    /// );
+    /// ```
+    pub fn with_layout(mut self, newline: T, indent: T, dedent: T) -> Self {
+        self.peeked = OnceCell::new();
+        self.inserted = None;
+        self.hooks_mut().unit = LengthUnit::Tokens;
+        self.hooks_mut().layout = Some(Rc::new(Layout {
+            newline,
+            indent,
+            dedent,
+        }));
+        self
     }
 
-    pub fn peek(&self) -> Option<(T, &'i str)> {
-        let mut iter = self.lexer.clone().spanned();
-        iter.next().map(|(t, span)| (t, &self.lexer.source()[span]))
+    /// Inserts a synthetic `terminator` token at line breaks after tokens for which
+    /// `ends_statement` returns `true`, like the automatic semicolon insertion in Go.
+    ///
+    /// A terminator is also inserted at the end of the input if the last token can end a
+    /// statement. It spans the line break, or is empty at the end of the input. Because
+    /// terminators don't advance the lexer, this also sets the [`LengthUnit`] to
+    /// [`LengthUnit::Tokens`].
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Token {
+    ///     #[token("=")]
+    ///     Assign,
+    ///
+    ///     #[token("+")]
+    ///     Plus,
+    ///
+    ///     #[token(";")]
+    ///     Semicolon,
+    ///
+    ///     #[regex("[a-z0-9]+")]
+    ///     Word,
+    /// }
+    ///
+    /// use logos_nom_bridge::Tokens;
+    /// use Token::*;
+    ///
+    /// let tokens = Tokens::new("a = 1 +\n 2\nb")
+    ///     .with_terminators(Semicolon, |t| matches!(t, Word));
+    ///
+    /// let slices: Vec<_> = tokens.clone().iter().map(|(_, _, s)| s).collect();
+    /// assert_eq!(slices, ["a", "=", "1", "+", "2", "\n", "b", ""]);
+    ///
+    /// // terminators are consumed like any other token
+    /// use logos_nom_bridge::combinators::{any_token, repeat_to_eof};
+    /// use nom::IResult;
+    ///
+    /// let result: IResult<_, _> = repeat_to_eof(any_token)(tokens);
+    /// let tokens: Vec<_> = result.unwrap().1.into_iter().map(|(t, _, _)| t).collect();
+    /// assert_eq!(tokens, [Word, Assign, Word, Plus, Word, Semicolon, Word, Semicolon]);
+    /// ```
+    pub fn with_terminators(
+        mut self,
+        terminator: T,
+        ends_statement: impl Fn(&T) -> bool + 'i,
+    ) -> Self {
+        self.peeked = OnceCell::new();
+        self.inserted = None;
+        self.hooks_mut().unit = LengthUnit::Tokens;
+        self.hooks_mut().terminator = Some(Rc::new(Terminator {
+            token: terminator,
+            ends_statement: Rc::new(ends_statement),
+        }));
+        self
+    }
+
+    /// Treats the source as a chunk of a larger input that isn't complete yet, e.g. because it
+    /// is still arriving over the network.
+    ///
+    /// A token that ends at the end of the chunk is not returned, since it might continue in
+    /// the next chunk. This makes nom's streaming parsers (like
+    /// [`nom::bytes::streaming::take`]) return [`nom::Err::Incomplete`] when they reach the end
+    /// of the chunk. Token parsers implemented with [`token_parser`] do the same. When more
+    /// input arrives, the caller should parse the extended source again from the start.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    /// use nom::{sequence::tuple, IResult};
+    ///
+    /// logos_nom_bridge::token_parser!(token: Token);
+    ///
+    /// // "12" might be the start of "123"
+    /// let tokens = Tokens::<Token>::new("1 + 12").streaming();
+    /// assert_eq!(tokens.iter().count(), 2);
+    ///
+    /// let result: IResult<_, _> = tuple((Token::Number(1), Token::Plus, Token::Number(12)))(tokens);
+    /// assert!(matches!(result, Err(nom::Err::Incomplete(_))));
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 12 ").streaming();
+    /// let result: IResult<_, _> = tuple((Token::Number(1), Token::Plus, Token::Number(12)))(tokens);
+    /// assert!(result.is_ok());
+    /// ```
+    pub fn streaming(mut self) -> Self {
+        self.peeked = OnceCell::new();
+        self.inserted = None;
+        self.hooks_mut().chunk_len = Some(self.lexer.source().len());
+        self
     }
 
-    pub fn advance(mut self) -> Self {
-        self.lexer.next();
+    /// Sets what happens when the lexer can't recognize the source text. By default, the
+    /// tokens end before the error ([`LexErrorPolicy::Stop`]).
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::{LexErrorPolicy, Tokens};
+    /// use nom::{branch::alt, multi::many0, IResult};
+    ///
+    /// logos_nom_bridge::token_parser!(token: Token);
+    ///
+    /// let tokens = Tokens::<Token>::new("+ ? + ! +");
+    ///
+    /// let skipping = tokens.clone().on_lex_error(LexErrorPolicy::Skip);
+    /// assert_eq!(skipping.iter().count(), 3);
+    ///
+    /// let failing = tokens.clone().on_lex_error(LexErrorPolicy::Fail);
+    /// let result: IResult<_, _> = alt((Token::Number(1), Token::Plus))(failing.advance());
+    /// assert!(matches!(result, Err(nom::Err::Failure(_))));
+    ///
+    /// let collecting = tokens.on_lex_error(LexErrorPolicy::Collect);
+    /// let result: IResult<_, _> = many0(Token::Plus)(collecting);
+    /// let (rest, _) = result.unwrap();
+    /// assert_eq!(rest.collected_lex_errors(), [((), 2..3), ((), 6..7)]);
+    /// ```
+    pub fn on_lex_error(mut self, policy: LexErrorPolicy) -> Self {
+        self.peeked = OnceCell::new();
+        self.hooks_mut().lex_error_policy = policy;
         self
     }
+
+    /// Lexes the source text in `span` with a different token type.
+    ///
+    /// The returned stream ends at `span.end`, but its spans are relative to the whole source,
+    /// so they can be used for diagnostics directly. This is useful for tokens that contain
+    /// nested syntax, such as interpolated strings. See also
+    /// [`parse_within`](combinators::parse_within).
+    ///
+    /// The [length unit](Tokens::with_length_unit), the [tracer](Tokens::with_tracer), the
+    /// [stats collector](Tokens::with_stats) and the [lexing error policy](Tokens::on_lex_error)
+    /// are kept. The returned stream is [streaming](Tokens::streaming) only if `span` ends where
+    /// the source received so far ends, since otherwise its end is known. The trivia sink isn't
+    /// kept, because the text in `span` usually belongs to a token of `self` that was already
+    /// consumed, and the [collected lexing errors](Tokens::collected_lex_errors) of `self` aren't
+    /// included. Like with [`Tokens::morph`], other configuration depends on the token type and
+    /// is discarded.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// #[logos(skip r"[ \t\n\f]+")]
+    /// enum Token {
+    ///     #[regex(r#"f"[^"]*""#)]
+    ///     FString,
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// enum Part {
+    ///     #[regex("[^{}]+")]
+    ///     Text,
+    ///
+    ///     #[token("{")]
+    ///     LBrace,
+    ///
+    ///     #[token("}")]
+    ///     RBrace,
+    /// }
+    ///
+    /// use logos_nom_bridge::{LexErrorPolicy, Tokens};
+    ///
+    /// let tokens = Tokens::<Token>::new(r#"  f"a {b}""#);
+    /// let (_, span, _) = tokens.peek_with_span().unwrap();
+    ///
+    /// let parts: Vec<_> = tokens.sublex::<Part>(span.start + 2..span.end - 1).iter().collect();
+    /// assert_eq!(
+    ///     parts,
+    ///     [
+    ///         (Part::Text, 4..6, "a "),
+    ///         (Part::LBrace, 6..7, "{"),
+    ///         (Part::Text, 7..8, "b"),
+    ///         (Part::RBrace, 8..9, "}"),
+    ///     ],
+    /// );
+    ///
+    /// let skipping = tokens.on_lex_error(LexErrorPolicy::Skip);
+    /// let parts = skipping.sublex::<Part>(span.start + 2..span.end - 1);
+    /// assert_eq!(parts.lex_error_policy(), LexErrorPolicy::Skip);
+    /// assert!(!parts.is_streaming());
+    /// ```
+    pub fn sublex<U>(&self, span: Span) -> Tokens<'i, U>
+    where
+        U: Logos<'i, Source = T::Source>,
+        U::Extras: Default,
+    {
+        let mut lexer = Lexer::new(self.lexer.source().truncate(span.end));
+        lexer.bump(span.start);
+        let mut tokens = Tokens::from_lexer(lexer);
+        let hooks = tokens.hooks_mut();
+        hooks.unit = self.hooks.unit;
+        hooks.chunk_len = self.hooks.chunk_len.filter(|&len| len == span.end);
+        hooks.lex_error_policy = self.hooks.lex_error_policy;
+        hooks.tracer = self.hooks.tracer.clone();
+        hooks.stats = self.hooks.stats.clone();
+        tokens
+    }
+
+    /// Runs `parser` on a copy of the input and returns how many bytes of the source it
+    /// consumed. The input itself is not advanced.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    /// use nom::sequence::tuple;
+    ///
+    /// logos_nom_bridge::token_parser!(token: Token);
+    ///
+    /// logos_nom_bridge::data_variant_parser! {
+    ///     fn parse_number(input) -> Result<i64>;
+    ///     pattern = Token::Number(n) => n;
+    /// }
+    ///
+    /// let tokens = Tokens::new("10 + 3 + 4");
+    /// let consumed = tokens.bytes_consumed_by(tuple((parse_number, Token::Plus, parse_number)));
+    ///
+    /// assert_eq!(consumed, Ok(6));
+    /// assert_eq!(tokens.len(), 10);
+    /// ```
+    pub fn bytes_consumed_by<O, E, P>(&self, mut parser: P) -> Result<usize, nom::Err<E>>
+    where
+        P: nom::Parser<Self, O, E>,
+    {
+        let (rest, _) = parser.parse(self.clone())?;
+        Ok(self.len() - rest.len())
+    }
+
+    /// Parses the whole input with `parser`, recovering from errors, and returns all
+    /// successfully parsed items and all errors that were encountered.
+    ///
+    /// When `parser` fails, the error is recorded and tokens are skipped up to the next token
+    /// contained in `sync`, which is not consumed, like in
+    /// [`recover_until`](combinators::recover_until) and
+    /// [`Recoverable::recover`](recovery::Recoverable::recover). Parsing resumes at that token,
+    /// so `sync` should contain the tokens that start an item. At least one token is skipped
+    /// after each error, so parsing can't get stuck. If the tokens end early at a
+    /// [lexing error](Tokens::lex_error), an error with [`ErrorKind::Eof`] is recorded for it.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("let")]
+    /// #     Let,
+    /// #
+    /// #     #[token("=")]
+    /// #     Eq,
+    /// #
+    /// #     #[token(";")]
+    /// #     Semicolon,
+    /// #
+    /// #     #[regex("[a-z]+")]
+    /// #     Ident,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    /// use nom::sequence::{delimited, tuple};
+    ///
+    /// logos_nom_bridge::token_parser!(token: Token);
+    ///
+    /// logos_nom_bridge::data_variant_parser! {
+    ///     fn parse_number(input) -> Result<i64>;
+    ///     pattern = Token::Number(n) => n;
+    /// }
+    ///
+    /// let statement = || {
+    ///     delimited(tuple((Token::Let, Token::Ident, Token::Eq)), parse_number, Token::Semicolon)
+    /// };
+    ///
+    /// let tokens = Tokens::new("let a = 1; let = 2; let c = 3;");
+    /// let (items, errors) = tokens.parse_resilient(statement(), &[Token::Let]);
+    ///
+    /// assert_eq!(items, [1, 3]);
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(errors[0].input.peek(), Some((Token::Eq, "=")));
+    ///
+    /// let tokens = Tokens::new("let a = 1; let b $ = 2;");
+    /// let (items, errors) = tokens.parse_resilient(statement(), &[Token::Let]);
+    ///
+    /// assert_eq!(items, [1]);
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[1].input.lex_error(), Some(((), 17..18)));
+    /// ```
+    ///
+    /// Synthetic tokens, like the ones inserted by [`Tokens::with_layout`], can be parsed as
+    /// items:
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// #
+    /// #     Newline,
+    /// #     Indent,
+    /// #     Dedent,
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    /// use nom::{branch::alt, combinator::value, error::Error, Parser};
+    ///
+    /// logos_nom_bridge::token_parser!(token: Token);
+    ///
+    /// logos_nom_bridge::data_variant_parser! {
+    ///     fn parse_number(input) -> Result<i64>;
+    ///     pattern = Token::Number(n) => n;
+    /// }
+    ///
+    /// let tokens = Tokens::new("1\n2\n3").with_layout(Token::Newline, Token::Indent, Token::Dedent);
+    /// let item = alt((parse_number.map(Some), value(None, Token::Newline)));
+    /// let (items, errors): (_, Vec<Error<_>>) = tokens.parse_resilient(item, &[Token::Newline]);
+    ///
+    /// assert_eq!(items, [Some(1), None, Some(2), None, Some(3), None]);
+    /// assert!(errors.is_empty());
+    /// ```
+    pub fn parse_resilient<O, E, P>(self, mut parser: P, sync: &[T]) -> (Vec<O>, Vec<E>)
+    where
+        T: PartialEq,
+        P: nom::Parser<Self, O, E>,
+        E: ParseError<Self>,
+    {
+        let mut input = self;
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+
+        while input.peek().is_some() {
+            match parser.parse(input.clone()) {
+                Ok((rest, item)) if rest != input => {
+                    items.push(item);
+                    input = rest;
+                    continue;
+                }
+                // infinite loop check: the parser must always consume
+                Ok(_) => errors.push(E::from_error_kind(input.clone(), ErrorKind::Many0)),
+                Err(nom::Err::Error(e) | nom::Err::Failure(e)) => errors.push(e),
+                Err(nom::Err::Incomplete(_)) => break,
+            }
+
+            // skip at least one token, so we can't get stuck
+            input = input.advance();
+            input = input.skip_while(|token| !sync.contains(token)).0;
+        }
+
+        if input.lex_error().is_some() {
+            errors.push(E::from_error_kind(input, ErrorKind::Eof));
+        }
+        (items, errors)
+    }
 }
 
+/// Two inputs are equal if they lex the same source, i.e. the same string and not just an
+/// equal one, and are at the same position, having consumed the same number of tokens. This
+/// takes constant time.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+///
+/// let (source, copy) = ("1 + 2", String::from("1 + 2"));
+/// let tokens = Tokens::<Token>::new(source);
+///
+/// assert_eq!(tokens.clone().advance(), tokens.clone().advance());
+/// assert_ne!(tokens.clone().advance(), tokens);
+/// assert_ne!(Tokens::<Token>::new(&copy), tokens);
+/// ```
 impl<'i, T> PartialEq for Tokens<'i, T>
 where
-    T: PartialEq + Logos<'i> + Clone,
-    T::Extras: Clone,
+    T: Logos<'i>,
 {
     fn eq(&self, other: &Self) -> bool {
-        Iterator::eq(self.lexer.clone(), other.lexer.clone())
+        core::ptr::eq(self.lexer.source(), other.lexer.source())
+            && self.lexer.span().end == other.lexer.span().end
+            && self.consumed == other.consumed
     }
 }
 
-impl<'i, T> Eq for Tokens<'i, T>
+impl<'i, T> Eq for Tokens<'i, T> where T: Logos<'i> {}
+
+/// Hashes the identity of the source and the position, consistent with [`PartialEq`], so inputs
+/// can be used as keys of hash maps, e.g. for memoization. This takes constant time.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+/// use std::collections::HashSet;
+///
+/// let tokens = Tokens::<Token>::new("1 + 2");
+/// let mut seen = HashSet::new();
+///
+/// assert!(seen.insert(tokens.clone()));
+/// assert!(seen.insert(tokens.clone().advance()));
+/// assert!(!seen.insert(tokens));
+/// ```
+impl<'i, T> Hash for Tokens<'i, T>
 where
-    T: Eq + Logos<'i> + Clone,
-    T::Extras: Clone,
+    T: Logos<'i>,
 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self.lexer.source(), state);
+        self.lexer.span().end.hash(state);
+        self.consumed.hash(state);
+    }
 }
 
+/// Shows the upcoming tokens with their source slices. The number of tokens can be set with
+/// [`Tokens::with_debug_preview`].
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+///
+/// let tokens = Tokens::<Token>::new("10 + 3 + 4").with_debug_preview(3);
+/// assert_eq!(
+///     format!("{tokens:?}"),
+///     r#"Tokens[Number(10) "10", Plus "+", Number(3) "3", …]"#,
+/// );
+/// assert_eq!(format!("{:?}", tokens.advance_n(3).0), r#"Tokens[Plus "+", Number(4) "4"]"#);
+/// ```
 impl<'i, T> fmt::Debug for Tokens<'i, T>
 where
-    T: fmt::Debug + Logos<'i, Source = str>,
+    T: fmt::Debug + Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    <T::Source as Source>::Slice: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let source = self.lexer.source();
-        let start = self.lexer.span().start;
-        f.debug_tuple("Tokens").field(&&source[start..]).finish()
+        struct Entry<T, S>(T, S);
+
+        impl<T: fmt::Debug, S: fmt::Debug> fmt::Debug for Entry<T, S> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{:?} {:?}", self.0, self.1)
+            }
+        }
+
+        f.write_str("Tokens")?;
+        let mut list = f.debug_list();
+        let mut tokens = self.lookahead();
+        for _ in 0..self.hooks.debug_preview {
+            match tokens.lex_next() {
+                Some((t, span)) => list.entry(&Entry(t, self.source_slice(span))),
+                None => return list.finish(),
+            };
+        }
+        if tokens.lex_next().is_some() {
+            list.entry(&format_args!("…"));
+        }
+        list.finish()
+    }
+}
+
+/// Shows the current [position](Tokens::position) and the beginning of the remaining source,
+/// for logging.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+///
+/// let tokens = Tokens::<Token>::new("1 +\n  2 + 3 + 4 + 5 + 6 + 7 + 8").advance_n(2).0;
+/// assert_eq!(tokens.to_string(), r#"1:4 (byte 3): "\n  2 + 3 + 4 + 5 + 6"…"#);
+///
+/// let tokens = Tokens::<Token>::new("1 + 2").advance_n(3).0;
+/// assert_eq!(tokens.to_string(), r#"1:6 (byte 5): """#);
+/// ```
+impl<'i, T> fmt::Display for Tokens<'i, T>
+where
+    T: Logos<'i, Source = str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const EXCERPT_LEN: usize = 20;
+
+        let SourcePosition {
+            byte, line, column, ..
+        } = self.position();
+        let rest = self.remainder();
+        let excerpt = match rest.char_indices().nth(EXCERPT_LEN) {
+            Some((i, _)) => &rest[..i],
+            None => rest,
+        };
+        write!(f, "{line}:{column} (byte {byte}): {excerpt:?}")?;
+        if excerpt.len() < rest.len() {
+            f.write_str("…")?;
+        }
+        Ok(())
     }
 }
 
@@ -217,109 +2404,601 @@ where
     T::Extras: Default,
 {
     fn default() -> Self {
-        Tokens {
-            lexer: Lexer::new(""),
-        }
+        Tokens::new("")
+    }
+}
+
+impl<'i, T> From<Lexer<'i, T>> for Tokens<'i, T>
+where
+    T: Logos<'i>,
+{
+    fn from(lexer: Lexer<'i, T>) -> Self {
+        Tokens::from_lexer(lexer)
+    }
+}
+
+/// An iterator over the remaining tokens of a [`Tokens`], yielding each token together with its
+/// span and source slice.
+///
+/// This is created by [`Tokens::iter`] or by iterating over a `Tokens` directly.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+///
+/// let tokens = Tokens::<Token>::new("1 + 2").advance();
+///
+/// let items: Vec<_> = tokens.iter().collect();
+/// assert_eq!(items, [(Token::Plus, 2..3, "+"), (Token::Number(2), 4..5, "2")]);
+///
+/// for (token, span, s) in tokens {
+///     println!("{token:?} at {span:?}: {s}");
+/// }
+/// ```
+pub struct Iter<'i, T>
+where
+    T: Logos<'i>,
+{
+    tokens: Tokens<'i, T>,
+}
+
+impl<'i, T> Iterator for Iter<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    type Item = (T, Span, &'i SourceSlice<'i, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tokens.lex_next().map(|(t, span)| {
+            let s = self.tokens.source_slice(span.clone());
+            (t, span, s)
+        })
+    }
+}
+
+impl<'i, T> IntoIterator for Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    type Item = (T, Span, &'i SourceSlice<'i, T>);
+
+    type IntoIter = Iter<'i, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter { tokens: self }
     }
 }
 
-/// An iterator, that (similarly to [`std::iter::Enumerate`]) produces byte offsets of the tokens.
+/// An iterator, that (similarly to [`std::iter::Enumerate`]) produces the offsets of the
+/// tokens, measured in the input's [`LengthUnit`].
 pub struct IndexIterator<'i, T>
 where
     T: Logos<'i>,
 {
-    logos: Lexer<'i, T>,
+    tokens: Tokens<'i, T>,
+    unit: LengthUnit,
+    start: usize,
+    /// The offset after the previous token
+    offset: usize,
 }
 
 impl<'i, T> Iterator for IndexIterator<'i, T>
 where
-    T: Logos<'i>,
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
 {
     type Item = (usize, (T, Span));
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.logos.next().map(|t| {
-            let span = self.logos.span();
-            (span.start, (t, span))
+        let offset = self.offset;
+        self.tokens.lex_next().map(|(t, span)| {
+            self.offset = match self.unit {
+                LengthUnit::Bytes => span.end - self.start,
+                LengthUnit::Tokens => offset + 1,
+            };
+            (offset, (t, span))
         })
     }
 }
 
 impl<'i, T> InputIter for Tokens<'i, T>
 where
-    T: Logos<'i, Source = str> + Clone,
-    T::Extras: Default + Clone,
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
 {
     type Item = (T, Span);
 
     type Iter = IndexIterator<'i, T>;
 
-    type IterElem = SpannedIter<'i, T>;
+    type IterElem = iter::Map<Iter<'i, T>, fn((T, Span, &'i SourceSlice<'i, T>)) -> (T, Span)>;
 
     fn iter_indices(&self) -> Self::Iter {
         IndexIterator {
-            logos: self.lexer.clone(),
+            tokens: self.lookahead(),
+            unit: self.unit(),
+            start: self.lexer.span().end,
+            offset: 0,
         }
     }
 
     fn iter_elements(&self) -> Self::IterElem {
-        self.lexer.clone().spanned()
+        self.iter().map(|(t, span, _)| (t, span))
     }
 
     fn position<P>(&self, predicate: P) -> Option<usize>
     where
         P: Fn(Self::Item) -> bool,
     {
-        let mut iter = self.lexer.clone().spanned();
-        iter.find(|t| predicate(t.clone()))
-            .map(|(_, span)| span.start)
+        self.iter_indices()
+            .find(|(_, t)| predicate(t.clone()))
+            .map(|(offset, _)| offset)
     }
 
     fn slice_index(&self, count: usize) -> Result<usize, nom::Needed> {
-        let mut cnt = 0;
-        for (_, span) in self.lexer.clone().spanned() {
-            if cnt == count {
-                return Ok(span.start);
-            }
-            cnt += 1;
+        if count == 0 {
+            return Ok(0);
         }
-        if cnt == count {
-            return Ok(self.len());
+        match self.iter_indices().nth(count - 1) {
+            Some((offset, (_, span))) => Ok(match self.unit() {
+                LengthUnit::Bytes => span.end - self.lexer.span().end,
+                LengthUnit::Tokens => offset + 1,
+            }),
+            None => Err(nom::Needed::Unknown),
         }
-        Err(nom::Needed::Unknown)
     }
 }
 
 impl<'i, T> InputLength for Tokens<'i, T>
 where
-    T: Logos<'i, Source = str> + Clone,
-    T::Extras: Default + Clone,
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
 {
     fn input_len(&self) -> usize {
-        self.len()
+        match self.unit() {
+            LengthUnit::Bytes => self.len(),
+            LengthUnit::Tokens => self.token_count(),
+        }
     }
 }
 
+/// Splits the input relative to the current position, with `count` measured in the input's
+/// [`LengthUnit`]. Both halves keep the spans of the original source and a copy of the lexer's
+/// extras.
+///
+/// When measuring in bytes, `count` should be obtained from [`InputIter::slice_index`] or
+/// [`InputIter::position`], so the input is split at a token boundary.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+/// use nom::{bytes::complete::take, IResult, InputIter};
+///
+/// let tokens = Tokens::<Token>::new("1 + 2 + 3").advance();
+/// let result: IResult<_, _> = take(2usize)(tokens);
+/// let (rest, taken) = result.unwrap();
+///
+/// let taken: Vec<_> = taken.iter_elements().collect();
+/// assert_eq!(taken, [(Token::Plus, 2..3), (Token::Number(2), 4..5)]);
+///
+/// let rest: Vec<_> = rest.iter_elements().collect();
+/// assert_eq!(rest, [(Token::Plus, 6..7), (Token::Number(3), 8..9)]);
+/// ```
 impl<'i, T> InputTake for Tokens<'i, T>
 where
-    T: Logos<'i, Source = str>,
-    T::Extras: Default,
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
 {
     fn take(&self, count: usize) -> Self {
-        Tokens {
-            lexer: Lexer::new(&self.lexer.source()[..count]),
-        }
+        let start = self.lexer.span().end;
+        let end = start + self.byte_offset(count);
+        self.with_lexer(self.lexer_at(start, end))
     }
 
     fn take_split(&self, count: usize) -> (Self, Self) {
-        let (a, b) = self.lexer.source().split_at(count);
+        let start = self.lexer.span().end;
+        let mid = start + self.byte_offset(count);
+        let end = self.lexer.source().len();
         (
-            Tokens {
-                lexer: Lexer::new(a),
-            },
-            Tokens {
-                lexer: Lexer::new(b),
-            },
+            self.skipped_to(mid, end, count),
+            self.with_lexer(self.lexer_at(start, mid)),
         )
     }
 }
+
+/// Returns the distance between two positions in the same source, measured in the input's
+/// [`LengthUnit`].
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{LengthUnit, Tokens};
+/// use nom::Offset;
+///
+/// let start = Tokens::<Token>::new("10 + 2");
+/// let end = start.clone().advance().advance();
+/// assert_eq!(start.offset(&end), 4);
+///
+/// let start = start.with_length_unit(LengthUnit::Tokens);
+/// let end = start.clone().advance().advance();
+/// assert_eq!(start.offset(&end), 2);
+/// ```
+impl<'i, T> Offset for Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn offset(&self, second: &Self) -> usize {
+        let start = self.lexer.span().end;
+        let end = second.lexer.span().end;
+        match self.unit() {
+            LengthUnit::Bytes => end - start,
+            LengthUnit::Tokens => self
+                .iter()
+                .take_while(|(_, span, _)| span.end <= end)
+                .count(),
+        }
+    }
+}
+
+/// Slices the input relative to the current position, with ranges measured in the input's
+/// [`LengthUnit`]. The result keeps the spans of the original source and a copy of the lexer's
+/// extras.
+///
+/// This makes it possible to use combinators like [`nom::combinator::recognize`]:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+/// use nom::{combinator::recognize, sequence::tuple, IResult, InputIter, Slice};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn parse_number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// let tokens = Tokens::new("1 + 2 + 3");
+/// let result: IResult<_, _> = recognize(tuple((parse_number, Token::Plus, parse_number)))(tokens);
+/// let (rest, sum) = result.unwrap();
+///
+/// let sum: Vec<_> = sum.iter_elements().collect();
+/// assert_eq!(sum, [(Token::Number(1), 0..1), (Token::Plus, 2..3), (Token::Number(2), 4..5)]);
+/// assert_eq!(rest.peek(), Some((Token::Plus, "+")));
+///
+/// let middle: Vec<_> = Tokens::<Token>::new("1 + 2").slice(1..3).iter_elements().collect();
+/// assert_eq!(middle, [(Token::Plus, 2..3)]);
+/// ```
+impl<'i, T> Slice<Range<usize>> for Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, range: Range<usize>) -> Self {
+        let pos = self.lexer.span().end;
+        let start = pos + self.byte_offset(range.start);
+        let end = pos + self.byte_offset(range.end);
+        self.skipped_to(start, end, range.start)
+    }
+}
+
+impl<'i, T> Slice<RangeTo<usize>> for Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, range: RangeTo<usize>) -> Self {
+        self.take(range.end)
+    }
+}
+
+impl<'i, T> Slice<RangeFrom<usize>> for Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, range: RangeFrom<usize>) -> Self {
+        let start = self.lexer.span().end + self.byte_offset(range.start);
+        let end = self.lexer.source().len();
+        self.skipped_to(start, end, range.start)
+    }
+}
+
+impl<'i, T> Slice<RangeFull> for Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn slice(&self, _: RangeFull) -> Self {
+        self.clone()
+    }
+}
+
+/// Compares the next tokens with a sequence of tokens, so [`nom::bytes::complete::tag`] can be
+/// used to match fixed token sequences.
+///
+/// **Note**: `tag` splits the input after as many units as there are tokens in the sequence, so
+/// the input must be measured in [`LengthUnit::Tokens`].
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("else")]
+/// #     Else,
+/// #
+/// #     #[token("if")]
+/// #     If,
+/// # }
+/// #
+/// use logos_nom_bridge::{LengthUnit, Tokens};
+/// use nom::{bytes::complete::tag, IResult};
+///
+/// let tokens = Tokens::new("else if if").with_length_unit(LengthUnit::Tokens);
+/// let result: IResult<_, _> = tag(&[Token::Else, Token::If][..])(tokens);
+/// let (rest, _) = result.unwrap();
+///
+/// assert_eq!(rest.peek(), Some((Token::If, "if")));
+/// ```
+impl<'a, 'i, T> Compare<&'a [T]> for Tokens<'i, T>
+where
+    T: Logos<'i> + PartialEq + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    fn compare(&self, t: &'a [T]) -> CompareResult {
+        let mut tokens = self.lookahead();
+        for expected in t {
+            match tokens.lex_next() {
+                Some((token, _)) if token == *expected => {}
+                Some(_) => return CompareResult::Error,
+                None => return CompareResult::Incomplete,
+            }
+        }
+        CompareResult::Ok
+    }
+
+    fn compare_no_case(&self, t: &'a [T]) -> CompareResult {
+        self.compare(t)
+    }
+}
+
+/// A set of tokens that implements [`FindToken`].
+///
+/// nom's `one_of` and `none_of` can't be used with [`Tokens`], because they require items that
+/// are characters, and Rust's orphan rules prevent implementing [`FindToken`] for slices and
+/// arrays of arbitrary token types. This wrapper can be used with combinators that accept any
+/// [`FindToken`] implementation instead, such as [`nom::bytes::complete::is_a`] and
+/// [`nom::bytes::complete::is_not`].
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[token("-")]
+/// #     Minus,
+/// #
+/// #     #[token("*")]
+/// #     Star,
+/// # }
+/// #
+/// use logos_nom_bridge::TokenSet;
+/// use nom::FindToken;
+///
+/// let set = TokenSet(&[Token::Plus, Token::Minus]);
+///
+/// assert!(set.find_token(Token::Plus));
+/// assert!(!set.find_token(Token::Star));
+/// assert!(set.find_token((Token::Minus, 0..1)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenSet<'a, T>(pub &'a [T]);
+
+impl<'a, T: PartialEq> FindToken<T> for TokenSet<'a, T> {
+    fn find_token(&self, token: T) -> bool {
+        self.0.contains(&token)
+    }
+}
+
+impl<'a, T: PartialEq> FindToken<(T, Span)> for TokenSet<'a, T> {
+    fn find_token(&self, (token, _): (T, Span)) -> bool {
+        self.0.contains(&token)
+    }
+}
+
+/// Splits the input at the first token for which a predicate returns `true`, so combinators
+/// like [`nom::bytes::complete::take_while`] and [`nom::bytes::complete::is_a`] can be used.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[token("-")]
+/// #     Minus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{TokenSet, Tokens};
+/// use nom::{
+///     bytes::complete::{is_a, take_while1},
+///     IResult, InputIter,
+/// };
+///
+/// let tokens = Tokens::new("1 2 + - 3");
+/// let result: IResult<_, _> = take_while1(|(t, _)| matches!(t, Token::Number(_)))(tokens);
+/// let (rest, numbers) = result.unwrap();
+/// assert_eq!(numbers.iter_elements().count(), 2);
+///
+/// let result: IResult<_, _> = is_a(TokenSet(&[Token::Plus, Token::Minus]))(rest);
+/// let (rest, signs) = result.unwrap();
+/// assert_eq!(signs.iter_elements().count(), 2);
+/// assert_eq!(rest.peek(), Some((Token::Number(3), "3")));
+///
+/// // the input is split before a lexing error, so it isn't lost
+/// let tokens = Tokens::new("1 2 $");
+/// let result: IResult<_, _> = take_while1(|(t, _)| matches!(t, Token::Number(_)))(tokens);
+/// let (rest, _) = result.unwrap();
+/// assert_eq!(rest.lex_error(), Some(((), 4..5)));
+/// ```
+impl<'i, T> InputTakeAtPosition for Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    type Item = (T, Span);
+
+    fn split_at_position<P, E: ParseError<Self>>(&self, predicate: P) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match InputIter::position(self, predicate) {
+            Some(n) => Ok(self.take_split(n)),
+            None => Err(nom::Err::Incomplete(nom::Needed::new(1))),
+        }
+    }
+
+    fn split_at_position1<P, E: ParseError<Self>>(
+        &self,
+        predicate: P,
+        e: ErrorKind,
+    ) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match InputIter::position(self, predicate) {
+            Some(0) => Err(nom::Err::Error(E::from_error_kind(self.clone(), e))),
+            Some(n) => Ok(self.take_split(n)),
+            None => Err(nom::Err::Incomplete(nom::Needed::new(1))),
+        }
+    }
+
+    fn split_at_position_complete<P, E: ParseError<Self>>(
+        &self,
+        predicate: P,
+    ) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match InputIter::position(self, predicate) {
+            Some(n) => Ok(self.take_split(n)),
+            None => Ok(self.take_split(self.tokens_len())),
+        }
+    }
+
+    fn split_at_position1_complete<P, E: ParseError<Self>>(
+        &self,
+        predicate: P,
+        e: ErrorKind,
+    ) -> IResult<Self, Self, E>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        match InputIter::position(self, predicate) {
+            Some(0) => Err(nom::Err::Error(E::from_error_kind(self.clone(), e))),
+            Some(n) => Ok(self.take_split(n)),
+            None if self.peek_spanned().is_none() => {
+                Err(nom::Err::Error(E::from_error_kind(self.clone(), e)))
+            }
+            None => Ok(self.take_split(self.tokens_len())),
+        }
+    }
+}
+
+/// Parses the remaining source, with surrounding whitespace removed, using [`str::parse`].
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"[0-9]+(\.[0-9]+)?")]
+/// #     Number,
+/// # }
+/// #
+/// use logos_nom_bridge::Tokens;
+/// use nom::ParseTo;
+///
+/// let tokens = Tokens::<Token>::new("1 + 4.5").advance().advance();
+/// assert_eq!(tokens.parse_to(), Some(4.5));
+/// ```
+impl<'i, T, R> ParseTo<R> for Tokens<'i, T>
+where
+    T: Logos<'i, Source = str>,
+    R: FromStr,
+{
+    fn parse_to(&self) -> Option<R> {
+        self.lexer.remainder().trim().parse().ok()
+    }
+}
@@ -0,0 +1,86 @@
+//! Conversion of byte offsets to line and column numbers.
+
+use alloc::vec::Vec;
+
+/// An index of the line starts in a source string.
+///
+/// Lines and columns are 1-based. Columns are counted in `char`s, not bytes.
+///
+/// ### Example
+///
+/// ```
+/// use logos_nom_bridge::line_index::LineIndex;
+///
+/// let index = LineIndex::new("let a = 1;\nlet ä = 2;\n");
+///
+/// assert_eq!(index.line_col(0), (1, 1));
+/// assert_eq!(index.line_col(4), (1, 5));
+/// assert_eq!(index.line_col(11), (2, 1));
+/// assert_eq!(index.line_col(17), (2, 6));
+///
+/// assert_eq!(index.offset(2, 6), Some(17));
+/// assert_eq!(index.offset(2, 11), Some(22));
+/// assert_eq!(index.offset(2, 12), None);
+/// ```
+///
+/// The index of the source of [`Tokens`](crate::Tokens) is available from
+/// [`Tokens::line_index`](crate::Tokens::line_index).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex<'i> {
+    source: &'i str,
+    line_starts: Vec<usize>,
+}
+
+impl<'i> LineIndex<'i> {
+    /// Builds the index by scanning `source` for newlines.
+    pub fn new(source: &'i str) -> Self {
+        let line_starts = core::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex {
+            source,
+            line_starts,
+        }
+    }
+
+    /// Returns the source string this index was built from.
+    pub fn source(&self) -> &'i str {
+        self.source
+    }
+
+    /// Returns the byte offsets at which the lines start.
+    #[cfg(feature = "codespan-reporting")]
+    pub(crate) fn line_starts(&self) -> &[usize] {
+        &self.line_starts
+    }
+
+    /// Returns the 1-based `(line, column)` of the byte `offset`.
+    ///
+    /// An offset past the end of the source is clamped to the end.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = self.source.as_bytes()[line_start..offset]
+            .iter()
+            .filter(|&&b| (b as i8) >= -0x40)
+            .count();
+        (line + 1, column + 1)
+    }
+
+    /// Returns the byte offset of the 1-based `line` and `column`, or `None` if the position is
+    /// outside of the source. The column after the last character of a line is valid.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        let text = &self.source[start..end];
+        text.char_indices()
+            .map(|(i, _)| i)
+            .chain(core::iter::once(text.len()))
+            .nth(column.checked_sub(1)?)
+            .map(|i| start + i)
+    }
+}
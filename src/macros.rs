@@ -1,4 +1,4 @@
-/// Automatically implements [`nom::Parser`] for your token type.
+/// Automatically implements [`nom::Parser`] for your token type and for references to it.
 ///
 /// ### Example
 ///
@@ -7,12 +7,50 @@
 /// enum Token {
 ///     #[token("test")]
 ///     Test,
+/// }
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+/// ```
+///
+/// Implementing the parser for references means tokens don't need to be cloned to be matched:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # enum Token {
+/// #     #[regex("[a-z]+", |lex| lex.slice().to_string())]
+/// #     Ident(String),
+/// # }
+/// #
+/// # logos_nom_bridge::token_parser!(token: Token);
+/// #
+/// use logos_nom_bridge::Tokens;
+/// use nom::{IResult, Parser};
+///
+/// let keyword = Token::Ident("test".to_string());
+///
+/// let result: IResult<_, _> = (&keyword).parse(Tokens::new("test"));
+/// assert_eq!(result.unwrap().1, "test");
+/// ```
+///
+/// The token type doesn't need to implement `Debug`:
 ///
-///     #[error]
-///     Error,
+/// ```
+/// #[derive(Clone, PartialEq, logos::Logos)]
+/// enum Token {
+///     #[token("+")]
+///     Plus,
+///
+///     #[token("-")]
+///     Minus,
 /// }
 ///
 /// logos_nom_bridge::token_parser!(token: Token);
+///
+/// use logos_nom_bridge::Tokens;
+/// use nom::{IResult, Parser};
+///
+/// let result: IResult<_, _> = Token::Plus.parse(Tokens::new("-"));
+/// assert!(result.is_err());
 /// ```
 ///
 /// You can use your own error type:
@@ -22,9 +60,6 @@
 /// # enum Token {
 /// #     #[token("test")]
 /// #     Test,
-/// #
-/// #     #[error]
-/// #     Error,
 /// # }
 /// #
 /// logos_nom_bridge::token_parser!(
@@ -44,9 +79,6 @@
 /// # enum Token {
 /// #     #[token("test")]
 /// #     Test,
-/// #
-/// #     #[error]
-/// #     Error,
 /// # }
 /// #
 /// logos_nom_bridge::token_parser!(
@@ -64,13 +96,134 @@
 ///     },
 /// }
 /// ```
+///
+/// Several token types, e.g. one per lexer mode, can share the same configuration:
+///
+/// ```
+/// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// enum Outer {
+///     #[token("\"")]
+///     Quote,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// enum Inner {
+///     #[token("\"")]
+///     Quote,
+/// }
+///
+/// logos_nom_bridge::token_parser!(
+///     tokens: [Outer, Inner],
+///     error: MyError = MyError::WrongToken,
+/// );
+///
+/// enum MyError {
+///     WrongToken,
+/// }
+/// ```
+///
+/// By default, the parsers return the matched source slice (`output: slice`). With `output: token`
+/// they return the matched token instead, with `output: ()` they return nothing:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # enum Token {
+/// #     #[regex("[a-z]+", |lex| lex.slice().to_string())]
+/// #     Ident(String),
+/// # }
+/// #
+/// logos_nom_bridge::token_parser!(token: Token, output: token);
+///
+/// use logos_nom_bridge::Tokens;
+/// use nom::{IResult, Parser};
+///
+/// let keyword = Token::Ident("test".to_string());
+///
+/// let result: IResult<_, _> = (&keyword).parse(Tokens::new("test"));
+/// assert_eq!(result.unwrap().1, keyword);
+/// ```
+///
+/// With `output: (token, span)`, they return the matched token and its span:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("let")]
+/// #     Let,
+/// # }
+/// #
+/// logos_nom_bridge::token_parser!(token: Token, output: (token, span));
+///
+/// use logos_nom_bridge::Tokens;
+/// use nom::{IResult, Parser};
+///
+/// let result: IResult<_, _> = Token::Let.parse(Tokens::new("  let"));
+/// assert_eq!(result.unwrap().1, (Token::Let, 2..5));
+/// ```
+///
+/// By default, tokens are compared with `==`. A different comparison, e.g. for case-insensitive
+/// keywords, can be specified with `compare`. It receives the expected and the found token:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[regex("[a-zA-Z]+", |lex| lex.slice().to_string())]
+/// #     Word(String),
+/// # }
+/// #
+/// logos_nom_bridge::token_parser!(
+///     token: Token,
+///     compare: |expected, found| match (expected, found) {
+///         (Token::Word(a), Token::Word(b)) => a.eq_ignore_ascii_case(b),
+///     },
+/// );
+///
+/// use logos_nom_bridge::Tokens;
+/// use nom::{sequence::pair, IResult};
+///
+/// let select = Token::Word("select".to_string());
+/// let from = Token::Word("from".to_string());
+///
+/// let result: IResult<_, _> = pair(&select, &from)(Tokens::new("SELECT From"));
+/// assert_eq!(result.unwrap().1, ("SELECT", "From"));
+/// ```
+///
+/// The output and compare options come before the error, if there is one:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # enum Token {
+/// #     #[token("test")]
+/// #     Test,
+/// # }
+/// #
+/// logos_nom_bridge::token_parser!(
+///     token: Token,
+///     output: (),
+///     error: MyError = MyError::WrongToken,
+/// );
+///
+/// enum MyError {
+///     WrongToken,
+/// }
+/// ```
 #[macro_export]
 macro_rules! token_parser {
     (
-        token: $token_ty:ty $(,)?
+        tokens: [$($token_ty:ty),+ $(,)?] $(, $($rest:tt)*)?
+    ) => {
+        $crate::__token_parsers!([$($token_ty),+] { $($($rest)*)? });
+    };
+
+    (
+        token: $token_ty:ty $(, output: $output:tt)? $(, compare: $compare:expr)? $(,)?
     ) => {
         $crate::token_parser!(
             token: $token_ty,
+            $(output: $output,)?
+            $(compare: $compare,)?
             error<'source>(input, token): ::nom::error::Error<$crate::Tokens<'source, $token_ty>> =
                 nom::error::Error::new(input, nom::error::ErrorKind::IsA),
         );
@@ -78,21 +231,27 @@ macro_rules! token_parser {
 
     (
         token: $token_ty:ty,
+        $(output: $output:tt,)?
+        $(compare: $compare:expr,)?
         error: $error_ty:ty = $error:expr $(,)?
     ) => {
         $crate::token_parser!(
             token: $token_ty,
+            $(output: $output,)?
+            $(compare: $compare,)?
             error<'source>(input, token): $error_ty = $error,
         );
     };
 
     (
         token: $token_ty:ty,
+        $(output: $output:tt,)?
+        $(compare: $compare:expr,)?
         error<$lt:lifetime>($input:ident, $token:ident): $error_ty:ty = $error:expr $(,)?
     ) => {
         impl<$lt> ::nom::Parser<
             $crate::Tokens<$lt, $token_ty>,
-            &$lt str,
+            $crate::__token_output!(type [$($output)?] $lt, $token_ty),
             $error_ty,
         > for $token_ty {
             fn parse(
@@ -100,16 +259,69 @@ macro_rules! token_parser {
                 $input: $crate::Tokens<$lt, $token_ty>,
             ) -> ::nom::IResult<
                 $crate::Tokens<$lt, $token_ty>,
-                &$lt str,
+                $crate::__token_output!(type [$($output)?] $lt, $token_ty),
                 $error_ty,
             > {
-                match $input.peek() {
-                    ::std::option::Option::Some((__token, __s)) if __token == *self => {
-                        ::std::result::Result::Ok(($input.advance(), __s))
+                match $input.peek_with_span() {
+                    ::core::option::Option::Some((__token, __span, __s))
+                        if $crate::__token_compare!([$($compare)?] $token_ty, &*self, &__token) =>
+                    {
+                        ::core::result::Result::Ok((
+                            $input.advance(),
+                            $crate::__token_output!(value [$($output)?] __token, __span, __s),
+                        ))
+                    }
+                    ::core::option::Option::None if $input.is_streaming() => {
+                        ::core::result::Result::Err(::nom::Err::Incomplete(::nom::Needed::Unknown))
+                    }
+                    ::core::option::Option::None if $input.is_at_fatal_lex_error() => {
+                        $crate::__trace_failure!($input, ::core::mem::discriminant(&*self));
+                        let $token = self;
+                        ::core::result::Result::Err(::nom::Err::Failure($error))
                     }
                     _ => {
+                        $crate::__trace_failure!($input, ::core::mem::discriminant(&*self));
                         let $token = self;
-                        ::std::result::Result::Err(::nom::Err::Error($error))
+                        ::core::result::Result::Err(::nom::Err::Error($error))
+                    },
+                }
+            }
+        }
+
+        impl<'__token, $lt> ::nom::Parser<
+            $crate::Tokens<$lt, $token_ty>,
+            $crate::__token_output!(type [$($output)?] $lt, $token_ty),
+            $error_ty,
+        > for &'__token $token_ty {
+            fn parse(
+                &mut self,
+                $input: $crate::Tokens<$lt, $token_ty>,
+            ) -> ::nom::IResult<
+                $crate::Tokens<$lt, $token_ty>,
+                $crate::__token_output!(type [$($output)?] $lt, $token_ty),
+                $error_ty,
+            > {
+                match $input.peek_with_span() {
+                    ::core::option::Option::Some((__token, __span, __s))
+                        if $crate::__token_compare!([$($compare)?] $token_ty, &**self, &__token) =>
+                    {
+                        ::core::result::Result::Ok((
+                            $input.advance(),
+                            $crate::__token_output!(value [$($output)?] __token, __span, __s),
+                        ))
+                    }
+                    ::core::option::Option::None if $input.is_streaming() => {
+                        ::core::result::Result::Err(::nom::Err::Incomplete(::nom::Needed::Unknown))
+                    }
+                    ::core::option::Option::None if $input.is_at_fatal_lex_error() => {
+                        $crate::__trace_failure!($input, ::core::mem::discriminant(*self));
+                        let $token = *self;
+                        ::core::result::Result::Err(::nom::Err::Failure($error))
+                    }
+                    _ => {
+                        $crate::__trace_failure!($input, ::core::mem::discriminant(*self));
+                        let $token = *self;
+                        ::core::result::Result::Err(::nom::Err::Error($error))
                     },
                 }
             }
@@ -124,12 +336,10 @@ macro_rules! token_parser {
 /// ```
 /// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
 /// enum Token {
-///     #[regex(r"-?[0-9]+", |lex| lex.slice().parse())]
+///     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
 ///     Number(i64),
 ///
 ///     // etc.
-/// #   #[error]
-/// #   Error,
 /// }
 ///
 /// enum Op {
@@ -149,11 +359,8 @@ macro_rules! token_parser {
 /// ```
 /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
 /// # enum Token {
-/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse())]
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
 /// #     Number(i64),
-/// #
-/// #     #[error]
-/// #     Error,
 /// # }
 /// #
 /// # enum Op {
@@ -178,11 +385,8 @@ macro_rules! token_parser {
 /// ```
 /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
 /// # enum Token {
-/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse())]
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
 /// #     Number(i64),
-/// #
-/// #     #[error]
-/// #     Error,
 /// # }
 /// #
 /// # enum Op {
@@ -205,55 +409,446 @@ macro_rules! token_parser {
 ///     error = MyError::WrongToken { input };
 /// }
 /// ```
+///
+/// A parser can accept several variants, with one `pattern` for each:
+///
+/// ```
+/// #[derive(Clone, Debug, PartialEq, logos::Logos)]
+/// #[logos(skip r"[ \t\n\f]+")]
+/// enum Token {
+///     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+///     Int(i64),
+///
+///     #[regex(r"-?[0-9]+\.[0-9]+", |lex| lex.slice().parse().ok())]
+///     Float(f64),
+/// }
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Lit {
+///     Int(i64),
+///     Float(f64),
+/// }
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn literal(input) -> Result<Lit>;
+///
+///     pattern = Token::Int(n) => Lit::Int(n);
+///     pattern = Token::Float(f) => Lit::Float(f);
+/// }
+///
+/// use logos_nom_bridge::Tokens;
+/// use nom::multi::many1;
+///
+/// let (_, lits) = many1(literal)(Tokens::new("1 2.5")).unwrap();
+/// assert_eq!(lits, [Lit::Int(1), Lit::Float(2.5)]);
+/// ```
+///
+/// Patterns can have guards, which reject tokens like a non-matching variant:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// logos_nom_bridge::data_variant_parser! {
+///     fn index(input) -> Result<usize>;
+///     pattern = Token::Number(n) if n >= 0 => n as usize;
+/// }
+///
+/// use logos_nom_bridge::Tokens;
+///
+/// assert!(index(Tokens::new("3")).is_ok());
+/// assert!(index(Tokens::new("-3")).is_err());
+/// ```
+///
+/// The source slice and the span of the matched token can be bound with `@ (slice, span)`:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[regex("[a-z]+", |lex| lex.slice().to_string())]
+/// #     Ident(String),
+/// # }
+/// #
+/// #[derive(Debug, PartialEq)]
+/// struct Var {
+///     name: String,
+///     span: logos::Span,
+/// }
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn var(input) -> Result<Var>;
+///     pattern = Token::Ident(_) @ (slice, span) => Var { name: slice.to_string(), span };
+/// }
+///
+/// let (_, var) = var(logos_nom_bridge::Tokens::new("  foo")).unwrap();
+/// assert_eq!(var, Var { name: "foo".to_string(), span: 2..5 });
+/// ```
+///
+/// The slice borrows from the source with the lifetime of the function, so the result can
+/// borrow it without copying. Without a lifetime parameter, the lifetime is named `'src`:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[regex("[a-z]+", |_| ())]
+/// #     Ident(()),
+/// #
+/// #     #[regex(r#""[^"]*""#, |_| ())]
+/// #     String(()),
+/// # }
+/// #
+/// #[derive(Debug, PartialEq)]
+/// enum Atom<'a> {
+///     Var(&'a str),
+///     Str(&'a str),
+/// }
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn atom<'a>(input) -> Result<Atom<'a>>;
+///
+///     pattern = Token::Ident(_) @ (name, _span) => Atom::Var(name);
+///     pattern = Token::String(_) @ (s, _span) => Atom::Str(&s[1..s.len() - 1]);
+/// }
+///
+/// use logos_nom_bridge::Tokens;
+/// use nom::multi::many1;
+///
+/// let (_, atoms) = many1(atom)(Tokens::new(r#"x "hello""#)).unwrap();
+/// assert_eq!(atoms, [Atom::Var("x"), Atom::Str("hello")]);
+/// ```
+///
+/// The function can have type parameters and a `where` clause. Bounds are trait paths without
+/// a leading `::`, combined with `+`:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// logos_nom_bridge::data_variant_parser! {
+///     fn number<N>(input) -> Result<N>
+///     where
+///         N: From<i64>;
+///
+///     pattern = Token::Number(n) => N::from(n);
+/// }
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn double<N: From<i64> + Copy>(input) -> Result<N>
+///     where
+///         N: core::ops::Add<Output = N> + PartialOrd;
+///
+///     pattern = Token::Number(n) => N::from(n) + N::from(n);
+/// }
+///
+/// use logos_nom_bridge::Tokens;
+///
+/// let (_, n) = number::<i128>(Tokens::new("100")).unwrap();
+/// assert_eq!(n, 100);
+///
+/// let (_, n) = double::<i128>(Tokens::new("100")).unwrap();
+/// assert_eq!(n, 200);
+/// ```
+///
+/// Attributes, including doc comments, and the visibility are applied to the generated function:
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # enum Token {
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// logos_nom_bridge::data_variant_parser! {
+///     /// Parses a number literal.
+///     #[inline]
+///     pub fn number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+/// ```
 #[macro_export]
 macro_rules! data_variant_parser {
     (
-        fn $fn_name:ident($input:ident) -> Result<$ok_ty:ty>;
+        $(#[$attr:meta])*
+        $vis:vis fn $fn_name:ident $(<$(
+            $param:ident $(: $(
+                $bound:ident $(:: $bound_seg:ident)*
+                $(<$($bound_arg:ty $(= $bound_arg_ty:ty)?),+>)? $(+)?
+            )+)?
+        ),+ $(,)?>)? ($input:ident)
+            -> Result<$ok_ty:ty>
+            $(where $(
+                $bounded:ty: $(
+                    $bounded_by:ident $(:: $bounded_by_seg:ident)*
+                    $(<$($bounded_by_arg:ty $(= $bounded_by_arg_ty:ty)?),+>)? $(+)?
+                )+
+            ),+ $(,)?)?;
+
+        $($body:tt)*
+    ) => {
+        $crate::data_variant_parser! {
+            $(#[$attr])*
+            $vis fn $fn_name<'src $($(
+                , $param $(: $(
+                    $bound $(:: $bound_seg)* $(<$($bound_arg $(= $bound_arg_ty)?),+>)? +
+                )+)?
+            )+)?>($input) -> Result<$ok_ty>
+                $(where $(
+                    $bounded: $(
+                        $bounded_by $(:: $bounded_by_seg)*
+                        $(<$($bounded_by_arg $(= $bounded_by_arg_ty)?),+>)? +
+                    )+
+                ),+)?;
+
+            $($body)*
+        }
+    };
+
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $fn_name:ident<$lt:lifetime $(
+            , $param:ident $(: $(
+                $bound:ident $(:: $bound_seg:ident)*
+                $(<$($bound_arg:ty $(= $bound_arg_ty:ty)?),+>)? $(+)?
+            )+)?
+        )* $(,)?>($input:ident)
+            -> Result<$ok_ty:ty>
+            $(where $(
+                $bounded:ty: $(
+                    $bounded_by:ident $(:: $bounded_by_seg:ident)*
+                    $(<$($bounded_by_arg:ty $(= $bounded_by_arg_ty:ty)?),+>)? $(+)?
+                )+
+            ),+ $(,)?)?;
 
-        pattern = $type:ident :: $variant:ident $data:tt => $res:expr;
+        pattern = $type:ident $($patterns:tt)*
     ) => {
         $crate::data_variant_parser! {
-            fn $fn_name<'src>($input) -> Result<
+            $(#[$attr])*
+            $vis fn $fn_name<$lt $(
+                , $param $(: $(
+                    $bound $(:: $bound_seg)* $(<$($bound_arg $(= $bound_arg_ty)?),+>)? +
+                )+)?
+            )*>($input) -> Result<
                 $ok_ty,
-                ::nom::error::Error<$crate::Tokens<'src, $type>>,
-            >;
+                ::nom::error::Error<$crate::Tokens<$lt, $type>>,
+            >
+                $(where $(
+                    $bounded: $(
+                        $bounded_by $(:: $bounded_by_seg)*
+                        $(<$($bounded_by_arg $(= $bounded_by_arg_ty)?),+>)? +
+                    )+
+                ),+)?;
 
-            pattern = $type :: $variant $data => $res;
+            pattern = $type $($patterns)*
             error = ::nom::error::Error::new($input, ::nom::error::ErrorKind::IsA);
         }
     };
 
     (
-        fn $fn_name:ident($input:ident) -> Result<$ok_ty:ty, $error_ty:ty $(,)?>;
+        $(#[$attr:meta])*
+        $vis:vis fn $fn_name:ident $(<$(
+            $param:ident $(: $(
+                $bound:ident $(:: $bound_seg:ident)*
+                $(<$($bound_arg:ty $(= $bound_arg_ty:ty)?),+>)? $(+)?
+            )+)?
+        ),+ $(,)?>)? ($input:ident)
+            -> Result<$ok_ty:ty, $error_ty:ty $(,)?>
+            $(where $(
+                $bounded:ty: $(
+                    $bounded_by:ident $(:: $bounded_by_seg:ident)*
+                    $(<$($bounded_by_arg:ty $(= $bounded_by_arg_ty:ty)?),+>)? $(+)?
+                )+
+            ),+ $(,)?)?;
 
-        pattern = $type:ident :: $variant:ident $data:tt => $res:expr;
-        error = $error:expr;
+        $($body:tt)*
     ) => {
         $crate::data_variant_parser! {
-            fn $fn_name<'src>($input) -> Result<$ok_ty, $error_ty>;
+            $(#[$attr])*
+            $vis fn $fn_name<'src $($(
+                , $param $(: $(
+                    $bound $(:: $bound_seg)* $(<$($bound_arg $(= $bound_arg_ty)?),+>)? +
+                )+)?
+            )+)?>($input) -> Result<$ok_ty, $error_ty>
+                $(where $(
+                    $bounded: $(
+                        $bounded_by $(:: $bounded_by_seg)*
+                        $(<$($bounded_by_arg $(= $bounded_by_arg_ty)?),+>)? +
+                    )+
+                ),+)?;
 
-            pattern = $type :: $variant $data => $res;
-            error = $error;
+            $($body)*
         }
     };
 
     (
-        fn $fn_name:ident<$lt:lifetime>($input:ident) -> Result<$ok_ty:ty, $error_ty:ty $(,)?>;
+        $(#[$attr:meta])*
+        $vis:vis fn $fn_name:ident<$lt:lifetime $(
+            , $param:ident $(: $(
+                $bound:ident $(:: $bound_seg:ident)*
+                $(<$($bound_arg:ty $(= $bound_arg_ty:ty)?),+>)? $(+)?
+            )+)?
+        )* $(,)?>($input:ident)
+            -> Result<$ok_ty:ty, $error_ty:ty $(,)?>
+            $(where $(
+                $bounded:ty: $(
+                    $bounded_by:ident $(:: $bounded_by_seg:ident)*
+                    $(<$($bounded_by_arg:ty $(= $bounded_by_arg_ty:ty)?),+>)? $(+)?
+                )+
+            ),+ $(,)?)?;
 
-        pattern = $type:ident :: $variant:ident $data:tt => $res:expr;
+        pattern = $type:ident :: $variant:ident $data:tt
+            $(@ ($slice:ident, $span:ident))? $(if $guard:expr)? => $res:expr;
+        $(
+            pattern = $types:ident :: $variants:ident $datas:tt
+                $(@ ($slices:ident, $spans:ident))? $(if $guards:expr)? => $results:expr;
+        )*
         error = $error:expr;
     ) => {
-        fn $fn_name<$lt>($input: $crate::Tokens<$lt, $type>) -> ::nom::IResult<
-            $crate::Tokens<$lt, $type>,
-            $ok_ty,
-            $error_ty,
-        > {
-            match $input.peek() {
-                ::std::option::Option::Some(($type::$variant $data, _)) => {
-                    Ok(($input.advance(), $res))
+        $(#[$attr])*
+        $vis fn $fn_name<$lt $(
+            , $param $(: $(
+                $bound $(:: $bound_seg)* $(<$($bound_arg $(= $bound_arg_ty)?),+>)? +
+            )+)?
+        )*>($input: $crate::Tokens<$lt, $type>)
+            -> ::nom::IResult<$crate::Tokens<$lt, $type>, $ok_ty, $error_ty>
+        $(where $(
+            $bounded: $(
+                $bounded_by $(:: $bounded_by_seg)*
+                $(<$($bounded_by_arg $(= $bounded_by_arg_ty)?),+>)? +
+            )+
+        ),+)?
+        {
+            match $input.peek_with_span() {
+                ::core::option::Option::Some((
+                    $type::$variant $data,
+                    $crate::__binding!($($span)?),
+                    $crate::__binding!($($slice)?),
+                )) $(if $guard)? => {
+                    ::core::result::Result::Ok(($input.advance(), $res))
+                }
+                $(
+                    ::core::option::Option::Some((
+                        $types::$variants $datas,
+                        $crate::__binding!($($spans)?),
+                        $crate::__binding!($($slices)?),
+                    )) $(if $guards)? => {
+                        ::core::result::Result::Ok(($input.advance(), $results))
+                    }
+                )*
+                ::core::option::Option::None if $input.is_streaming() => {
+                    ::core::result::Result::Err(::nom::Err::Incomplete(::nom::Needed::Unknown))
+                }
+                ::core::option::Option::None if $input.is_at_fatal_lex_error() => {
+                    $crate::__trace_failure!(
+                        $input,
+                        ::core::format_args!(::core::concat!(
+                            ::core::stringify!($type),
+                            "::",
+                            ::core::stringify!($variant),
+                            $(" or ", ::core::stringify!($types), "::", ::core::stringify!($variants),)*
+                        ))
+                    );
+                    ::core::result::Result::Err(::nom::Err::Failure($error))
+                }
+                _ => {
+                    $crate::__trace_failure!(
+                        $input,
+                        ::core::format_args!(::core::concat!(
+                            ::core::stringify!($type),
+                            "::",
+                            ::core::stringify!($variant),
+                            $(" or ", ::core::stringify!($types), "::", ::core::stringify!($variants),)*
+                        ))
+                    );
+                    ::core::result::Result::Err(::nom::Err::Error($error))
                 }
-                _ => ::std::result::Result::Err(::nom::Err::Error($error)),
             }
         }
     };
 }
+
+/// Expands to the given identifier pattern, or to `_` if there is none.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __binding {
+    () => {
+        _
+    };
+    ($name:ident) => {
+        $name
+    };
+}
+
+/// Invokes `token_parser!` for each token type in the list with the same configuration.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __token_parsers {
+    ([] { $($rest:tt)* }) => {};
+
+    ([$first:ty $(, $more:ty)*] { $($rest:tt)* }) => {
+        $crate::token_parser!(token: $first, $($rest)*);
+        $crate::__token_parsers!([$($more),*] { $($rest)* });
+    };
+}
+
+/// Expands to the output type or value of the parsers generated by `token_parser!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __token_output {
+    (type [$(slice)?] $lt:lifetime, $token_ty:ty) => { &$lt $crate::SourceSlice<$lt, $token_ty> };
+    (type [token] $lt:lifetime, $token_ty:ty) => { $token_ty };
+    (type [()] $lt:lifetime, $token_ty:ty) => { () };
+    (type [(token, span)] $lt:lifetime, $token_ty:ty) => { ($token_ty, ::core::ops::Range<usize>) };
+
+    (value [$(slice)?] $token:ident, $span:ident, $slice:ident) => { $slice };
+    (value [token] $token:ident, $span:ident, $slice:ident) => { $token };
+    (value [()] $token:ident, $span:ident, $slice:ident) => { () };
+    (value [(token, span)] $token:ident, $span:ident, $slice:ident) => { ($token, $span) };
+}
+
+/// Expands to the comparison of the expected and the found token in the parsers generated by
+/// `token_parser!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __token_compare {
+    ([] $token_ty:ty, $expected:expr, $found:expr) => {
+        *$found == *$expected
+    };
+
+    ([$compare:expr] $token_ty:ty, $expected:expr, $found:expr) => {{
+        let __compare: fn(&$token_ty, &$token_ty) -> bool = $compare;
+        __compare($expected, $found)
+    }};
+}
+
+/// Emits a `trace!` event for a failed parser if the `tracing` feature is enabled. The found
+/// token is logged by its span and slice, so the token type doesn't need to implement `Debug`.
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_failure {
+    ($input:ident, $expected:expr) => {
+        $crate::__tracing::trace!(
+            target: "logos_nom_bridge",
+            expected = ?$expected,
+            found = ?$input.peek_with_span().map(|(_, span, slice)| (span, slice)),
+            "parser failed",
+        )
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_failure {
+    ($input:ident, $expected:expr) => {};
+}
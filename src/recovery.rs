@@ -0,0 +1,213 @@
+//! Panic-mode error recovery, for parsers that should produce a partial result and a list of
+//! errors instead of stopping at the first error.
+//!
+//! Errors are recorded in a [`Recoverable`] sink that is shared by the parsers. When a parser
+//! wrapped with [`Recoverable::recover`] fails, its error is recorded, tokens are skipped up to
+//! a synchronization token, and a placeholder is returned in place of the output.
+//! [`parse_recovering`] runs the top-level parser and returns the result with all errors.
+//!
+//! ### Example
+//!
+//! ```
+//! # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+//! # #[logos(skip r"[ \t\n\f]+")]
+//! # enum Token {
+//! #     #[token("let")]
+//! #     Let,
+//! #
+//! #     #[token("=")]
+//! #     Eq,
+//! #
+//! #     #[token(";")]
+//! #     Semicolon,
+//! #
+//! #     #[regex("[a-z]+")]
+//! #     Ident,
+//! #
+//! #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+//! #     Number(i64),
+//! # }
+//! #
+//! use logos_nom_bridge::{recovery::parse_recovering, Tokens};
+//! use nom::{
+//!     combinator::cut,
+//!     multi::many0,
+//!     sequence::{preceded, separated_pair, terminated},
+//!     Parser,
+//! };
+//!
+//! logos_nom_bridge::token_parser!(token: Token);
+//!
+//! logos_nom_bridge::data_variant_parser! {
+//!     fn number(input) -> Result<i64>;
+//!     pattern = Token::Number(n) => n;
+//! }
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Stmt<'i> {
+//!     Let(&'i str, i64),
+//!     Error,
+//! }
+//!
+//! let tokens = Tokens::new("let a = 1; let = 2; let c = 3;");
+//! let (ast, errors) = parse_recovering(tokens, |sink| {
+//!     let binding = separated_pair(Token::Ident, Token::Eq, number);
+//!     let stmt = sink.recover(
+//!         &[Token::Semicolon],
+//!         |_span| Stmt::Error,
+//!         preceded(Token::Let, binding).map(|(name, n)| Stmt::Let(name, n)),
+//!     );
+//!     many0(terminated(stmt, Token::Semicolon))
+//! });
+//!
+//! assert_eq!(ast.unwrap(), [Stmt::Let("a", 1), Stmt::Error, Stmt::Let("c", 3)]);
+//! assert_eq!(errors.len(), 1);
+//! assert_eq!(errors[0].input.peek(), Some((Token::Eq, "=")));
+//!
+//! // `cut` stops the recovery
+//! let tokens = Tokens::new("let a = 1; let = 2;");
+//! let (ast, errors) = parse_recovering(tokens, |sink| {
+//!     let binding = separated_pair(Token::Ident, Token::Eq, number);
+//!     let stmt = sink.recover(
+//!         &[Token::Semicolon],
+//!         |_span| Stmt::Error,
+//!         preceded(Token::Let, cut(binding)).map(|(name, n)| Stmt::Let(name, n)),
+//!     );
+//!     many0(terminated(stmt, Token::Semicolon))
+//! });
+//!
+//! assert_eq!(ast, None);
+//! assert_eq!(errors.len(), 1);
+//! ```
+
+use alloc::{rc::Rc, vec::Vec};
+use core::cell::RefCell;
+
+use logos::{Logos, Span};
+use nom::{
+    error::{ErrorKind, ParseError},
+    IResult, Parser,
+};
+
+use crate::{combinators::consumed_span, TokenSource, Tokens};
+
+/// A shared sink for the errors that parsers recovered from. Clones of a `Recoverable` record
+/// into the same list.
+///
+/// Errors recorded by a parser stay in the sink even if a surrounding parser backtracks.
+pub struct Recoverable<E> {
+    errors: Rc<RefCell<Vec<E>>>,
+}
+
+impl<E> Recoverable<E> {
+    pub fn new() -> Self {
+        Recoverable {
+            errors: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Records an error.
+    pub fn emit(&self, error: E) {
+        self.errors.borrow_mut().push(error);
+    }
+
+    /// Returns the number of recorded errors.
+    pub fn len(&self) -> usize {
+        self.errors.borrow().len()
+    }
+
+    /// Returns `true` if no errors were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.errors.borrow().is_empty()
+    }
+
+    /// Removes and returns the recorded errors.
+    pub fn take(&self) -> Vec<E> {
+        core::mem::take(&mut *self.errors.borrow_mut())
+    }
+
+    /// Applies `parser`, recovering if it fails.
+    ///
+    /// If `parser` fails with [`nom::Err::Error`], the error is recorded, tokens are skipped
+    /// until the next token contained in `sync`, which is not consumed, and `placeholder` is
+    /// called with the span of the skipped tokens to create the output. At the end of the
+    /// input, there is nothing to recover from, so the error is returned.
+    ///
+    /// [`nom::Err::Failure`], e.g. from [`nom::combinator::cut`], and [`nom::Err::Incomplete`]
+    /// are returned unchanged.
+    pub fn recover<'a, 'i, T, O, P, F>(
+        &self,
+        sync: &'a [T],
+        mut placeholder: F,
+        mut parser: P,
+    ) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, O, E> + 'a
+    where
+        T: Logos<'i> + PartialEq + Clone,
+        T::Source: TokenSource,
+        T::Extras: Clone,
+        E: 'a,
+        P: Parser<Tokens<'i, T>, O, E> + 'a,
+        F: FnMut(Span) -> O + 'a,
+    {
+        let sink = self.clone();
+        move |input| match parser.parse(input.clone()) {
+            Err(nom::Err::Error(e)) if input.peek_spanned().is_some() => {
+                sink.emit(e);
+                let (rest, _) = input.clone().skip_while(|token| !sync.contains(token));
+                let span = consumed_span(&input, &rest);
+                Ok((rest, placeholder(span)))
+            }
+            result => result,
+        }
+    }
+}
+
+impl<E> Clone for Recoverable<E> {
+    fn clone(&self) -> Self {
+        Recoverable {
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+impl<E> Default for Recoverable<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses the whole input with the parser returned by `make_parser`, which receives the sink
+/// in which recovered errors are recorded. Returns the output, if the parser succeeded, and all
+/// errors in the order they were recorded.
+///
+/// If the parser fails, or doesn't consume the whole input, that error is appended to the
+/// recorded errors. See the [module-level docs](self) for an example.
+pub fn parse_recovering<'i, T, O, E, F, P>(
+    input: Tokens<'i, T>,
+    make_parser: F,
+) -> (Option<O>, Vec<E>)
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    E: ParseError<Tokens<'i, T>>,
+    F: FnOnce(Recoverable<E>) -> P,
+    P: Parser<Tokens<'i, T>, O, E>,
+{
+    let sink = Recoverable::new();
+    let result = make_parser(sink.clone()).parse(input);
+    let mut errors = sink.take();
+    match result {
+        Ok((rest, output)) => {
+            if rest.peek_spanned().is_some() {
+                errors.push(E::from_error_kind(rest, ErrorKind::Eof));
+            }
+            (Some(output), errors)
+        }
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            errors.push(e);
+            (None, errors)
+        }
+        Err(nom::Err::Incomplete(_)) => (None, errors),
+    }
+}
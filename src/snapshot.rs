@@ -0,0 +1,60 @@
+//! Serializable copies of the remaining tokens.
+
+use alloc::vec::Vec;
+
+use logos::{Logos, Span};
+use serde::Serialize;
+
+use crate::{SourceSlice, TokenSource, Tokens};
+
+/// A copy of the remaining tokens of a [`Tokens`], as `(token, span, slice)` triples. It is
+/// returned by [`Tokens::snapshot`] and serializes as a sequence of triples, which is useful for
+/// golden tests.
+///
+/// Requires the `serde` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Snapshot<'i, T, S: ?Sized = str> {
+    pub tokens: Vec<(T, Span, &'i S)>,
+}
+
+impl<'i, T> Tokens<'i, T>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+{
+    /// Returns a [`Snapshot`] of the remaining tokens. This lexes the rest of the input, but
+    /// doesn't consume it.
+    ///
+    /// Requires the `serde` feature.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos, serde::Serialize)]
+    /// # #[logos(skip r"[ \t\n\f]+")]
+    /// # enum Token {
+    /// #     #[token("+")]
+    /// #     Plus,
+    /// #
+    /// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    /// #     Number(i64),
+    /// # }
+    /// #
+    /// use logos_nom_bridge::Tokens;
+    ///
+    /// let tokens = Tokens::<Token>::new("1 + 20").advance();
+    /// let json = serde_json::to_string(&tokens.snapshot()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     json,
+    ///     r#"[["Plus",{"start":2,"end":3},"+"],[{"Number":20},{"start":4,"end":6},"20"]]"#,
+    /// );
+    /// ```
+    pub fn snapshot(&self) -> Snapshot<'i, T, SourceSlice<'i, T>> {
+        Snapshot {
+            tokens: self.iter().collect(),
+        }
+    }
+}
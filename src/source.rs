@@ -0,0 +1,73 @@
+//! The source types that [`Tokens`](crate::Tokens) can lex.
+
+use logos::Source;
+
+/// A source type that [`Tokens`](crate::Tokens) can lex. It is implemented for `str` and
+/// `[u8]`, the sources supported by logos, and can be implemented for custom
+/// [`logos::Source`] types.
+///
+/// Slices of the source, e.g. the ones returned by [`Tokens::peek`](crate::Tokens::peek), have
+/// the type [`SourceSlice`](crate::SourceSlice), which is `str` or `[u8]`.
+///
+/// ### Example
+///
+/// ```
+/// #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// #[logos(skip b" +")]
+/// enum Token {
+///     #[token(b"\xFF")]
+///     Start,
+///
+///     #[regex(b"[a-z]+")]
+///     Word,
+/// }
+///
+/// use logos_nom_bridge::{
+///     combinators::{end, keyword},
+///     Tokens,
+/// };
+/// use nom::{
+///     sequence::{pair, terminated},
+///     IResult,
+/// };
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// let tokens = Tokens::<Token>::new(b"\xFF ab cd");
+/// assert_eq!(tokens.peek(), Some((Token::Start, &b"\xFF"[..])));
+///
+/// let result: IResult<_, _> = pair(Token::Start, Token::Word)(tokens);
+/// let (rest, (start, word)) = result.unwrap();
+/// assert_eq!((start, word), (&b"\xFF"[..], &b"ab"[..]));
+/// assert_eq!(rest.peek(), Some((Token::Word, &b"cd"[..])));
+///
+/// let result: IResult<_, _> = terminated(keyword(Token::Word, &b"cd"[..]), end)(rest);
+/// assert_eq!(result.unwrap().1, &b"cd"[..]);
+/// ```
+pub trait TokenSource: Source {
+    /// Returns the source up to the byte offset `end`.
+    fn truncate(&self, end: usize) -> &Self;
+
+    /// Returns the source as bytes.
+    fn as_bytes(&self) -> &[u8];
+}
+
+impl TokenSource for str {
+    fn truncate(&self, end: usize) -> &Self {
+        &self[..end]
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+}
+
+impl TokenSource for [u8] {
+    fn truncate(&self, end: usize) -> &Self {
+        &self[..end]
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
@@ -0,0 +1,145 @@
+//! Helper functions for working with [`Span`]s, the byte ranges of tokens in the source, and
+//! the [`Spanned`] wrapper for values with a span.
+//!
+//! ### Example
+//!
+//! ```
+//! # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+//! # #[logos(skip r"[ \t\n\f]+")]
+//! # enum Token {
+//! #     #[token("+")]
+//! #     Plus,
+//! #
+//! #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+//! #     Number(i64),
+//! # }
+//! #
+//! use logos_nom_bridge::{span, Tokens};
+//!
+//! let (_, tokens) = Tokens::<Token>::new("10 + 2").advance_n(3);
+//! let (first, last) = (&tokens[0].1, &tokens[2].1);
+//!
+//! let sum = span::merge(first, last);
+//! assert_eq!(sum, 0..6);
+//! assert!(span::contains(&sum, &tokens[1].1));
+//! assert_eq!(span::len(&sum), 6);
+//!
+//! assert_eq!(span::to_relative(last, sum.start + 3), 2..3);
+//! assert_eq!(span::to_absolute(&(2..3), sum.start + 3), 5..6);
+//! ```
+
+use core::ops::{Deref, DerefMut};
+
+use logos::Span;
+
+/// Returns the smallest span that covers both `a` and `b`, including the text between them.
+pub fn merge(a: &Span, b: &Span) -> Span {
+    a.start.min(b.start)..a.end.max(b.end)
+}
+
+/// Returns the smallest span that covers all `spans`, or `None` if there are none.
+pub fn merge_all<'a>(spans: impl IntoIterator<Item = &'a Span>) -> Option<Span> {
+    spans
+        .into_iter()
+        .cloned()
+        .reduce(|acc, span| merge(&acc, &span))
+}
+
+/// Returns `true` if `inner` lies completely within `outer`.
+pub fn contains(outer: &Span, inner: &Span) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+/// Returns `true` if the spans share at least one byte.
+pub fn overlaps(a: &Span, b: &Span) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Returns the length of the span in bytes. Spans that end before they start are empty.
+pub fn len(span: &Span) -> usize {
+    span.end.saturating_sub(span.start)
+}
+
+/// Converts a span to be relative to the byte offset `base`, e.g. the start of the enclosing
+/// span. `span` must not start before `base`.
+pub fn to_relative(span: &Span, base: usize) -> Span {
+    span.start - base..span.end - base
+}
+
+/// Converts a span that is relative to the byte offset `base` back to an absolute span. This
+/// is the inverse of [`to_relative`].
+pub fn to_absolute(span: &Span, base: usize) -> Span {
+    span.start + base..span.end + base
+}
+
+/// A value together with the span of the source it was parsed from. This is returned by
+/// [`spanned`](crate::combinators::spanned) and
+/// [`delimited_list_spanned`](crate::combinators::delimited_list_spanned).
+///
+/// `Spanned<T>` dereferences to `T`, so methods of the value can be called directly.
+///
+/// ### Example
+///
+/// ```
+/// use logos_nom_bridge::span::Spanned;
+///
+/// let name = Spanned::new("answer", 4..10);
+/// assert_eq!(name.len(), 6);
+///
+/// let upper = name.as_ref().map(|s| s.to_uppercase());
+/// assert_eq!(upper, Spanned::new("ANSWER".to_string(), 4..10));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Self {
+        Spanned { value, span }
+    }
+
+    /// Converts the value with `f`, keeping the span.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned {
+            value: f(self.value),
+            span: self.span,
+        }
+    }
+
+    /// Returns a `Spanned` that borrows the value.
+    pub fn as_ref(&self) -> Spanned<&T> {
+        Spanned {
+            value: &self.value,
+            span: self.span.clone(),
+        }
+    }
+
+    /// Returns a `Spanned` that borrows the value mutably.
+    pub fn as_mut(&mut self) -> Spanned<&mut T> {
+        Spanned {
+            value: &mut self.value,
+            span: self.span.clone(),
+        }
+    }
+
+    /// Returns the value and the span.
+    pub fn into_parts(self) -> (T, Span) {
+        (self.value, self.span)
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
@@ -0,0 +1,84 @@
+//! Collection of statistics about a parse, for tuning the performance of grammars.
+
+use alloc::rc::Rc;
+use core::cell::Cell;
+
+/// Counters collected by [`ParseStats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Stats {
+    /// The number of tokens lexed, including tokens that were only peeked at or lexed more than
+    /// once.
+    pub tokens_lexed: usize,
+    /// The number of tokens consumed.
+    pub tokens_consumed: usize,
+    /// The number of tokens consumed again after backtracking, i.e. before the furthest position
+    /// that was already reached.
+    pub tokens_reconsumed: usize,
+    /// The number of times the input was cloned.
+    pub clones: usize,
+    /// The furthest byte offset reached by consuming tokens.
+    pub furthest: usize,
+}
+
+/// A shared collector of [`Stats`]. Attach it to the input with
+/// [`Tokens::with_stats`](crate::Tokens::with_stats), then read the counters after parsing.
+/// Clones of a `ParseStats` update the same counters.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token("+")]
+/// #     Plus,
+/// #
+/// #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{stats::ParseStats, Tokens};
+/// use nom::{branch::alt, sequence::tuple, IResult};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// let stats = ParseStats::new();
+/// let tokens = Tokens::<Token>::new("1 + 2").with_stats(stats.clone());
+///
+/// let result: IResult<_, _> = alt((
+///     tuple((Token::Number(1), Token::Plus, Token::Plus)),
+///     tuple((Token::Number(1), Token::Plus, Token::Number(2))),
+/// ))(tokens);
+/// assert!(result.is_ok());
+///
+/// let stats = stats.get();
+/// assert_eq!(stats.tokens_consumed, 5);
+/// assert_eq!(stats.tokens_reconsumed, 2);
+/// assert_eq!(stats.furthest, 5);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParseStats {
+    stats: Rc<Cell<Stats>>,
+}
+
+impl ParseStats {
+    pub fn new() -> Self {
+        ParseStats::default()
+    }
+
+    /// Returns the current counters.
+    pub fn get(&self) -> Stats {
+        self.stats.get()
+    }
+
+    /// Resets all counters to zero.
+    pub fn reset(&self) {
+        self.stats.set(Stats::default());
+    }
+
+    pub(crate) fn update(&self, f: impl FnOnce(&mut Stats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+}
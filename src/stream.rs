@@ -0,0 +1,168 @@
+//! A buffer for source text that arrives in chunks.
+
+use alloc::{string::String, vec::Vec};
+use core::str::{self, Utf8Error};
+
+use logos::Logos;
+
+use crate::Tokens;
+
+/// Accumulates source text that arrives in chunks, e.g. over a network connection, and
+/// provides [`Tokens`] over the text received so far.
+///
+/// Until [`StreamBuffer::finish`] is called, the tokens are [streaming](Tokens::streaming), so
+/// parsers return [`nom::Err::Incomplete`] when they need more data. After a successful parse,
+/// the parsed text can be removed with [`StreamBuffer::consume`]; spans are relative to the
+/// start of the remaining text.
+///
+/// With the `futures-io` feature, chunks can be read from an `AsyncRead` with
+/// `StreamBuffer::fill`.
+///
+/// ### Example
+///
+/// ```
+/// # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+/// # #[logos(skip r"[ \t\n\f]+")]
+/// # enum Token {
+/// #     #[token(";")]
+/// #     Semicolon,
+/// #
+/// #     #[regex(r"[0-9]+", |lex| lex.slice().parse().ok())]
+/// #     Number(i64),
+/// # }
+/// #
+/// use logos_nom_bridge::{StreamBuffer, Tokens};
+/// use nom::{sequence::terminated, IResult};
+///
+/// logos_nom_bridge::token_parser!(token: Token);
+///
+/// logos_nom_bridge::data_variant_parser! {
+///     fn number(input) -> Result<i64>;
+///     pattern = Token::Number(n) => n;
+/// }
+///
+/// fn message(input: Tokens<'_, Token>) -> IResult<Tokens<'_, Token>, i64> {
+///     terminated(number, Token::Semicolon)(input)
+/// }
+///
+/// let mut buffer = StreamBuffer::new();
+/// let mut messages = Vec::new();
+///
+/// for chunk in ["1", "2; 3", "4;", " 5"] {
+///     buffer.push(chunk.as_bytes()).unwrap();
+///     loop {
+///         let len = match message(buffer.tokens()) {
+///             Ok((rest, n)) => {
+///                 messages.push(n);
+///                 rest.current_span().end
+///             }
+///             Err(nom::Err::Incomplete(_)) => break,
+///             Err(e) => panic!("{e}"),
+///         };
+///         buffer.consume(len);
+///     }
+/// }
+///
+/// assert_eq!(messages, [12, 34]);
+/// assert_eq!(buffer.as_str(), " 5");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StreamBuffer {
+    text: String,
+    /// The start of a UTF-8 character that was split between chunks
+    partial_char: Vec<u8>,
+    finished: bool,
+}
+
+impl StreamBuffer {
+    pub fn new() -> Self {
+        StreamBuffer::default()
+    }
+
+    /// Appends a chunk of UTF-8 encoded text. A multi-byte character may be split between two
+    /// chunks.
+    ///
+    /// Returns an error if the chunk contains invalid UTF-8. In that case, the buffer is
+    /// unchanged.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), Utf8Error> {
+        let mut bytes = core::mem::take(&mut self.partial_char);
+        bytes.extend_from_slice(chunk);
+        let valid = match str::from_utf8(&bytes) {
+            Ok(text) => text,
+            Err(e) if e.error_len().is_none() => {
+                // The chunk ends in the middle of a character
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                self.partial_char = rest.to_vec();
+                str::from_utf8(valid).unwrap()
+            }
+            Err(e) => {
+                bytes.truncate(bytes.len() - chunk.len());
+                self.partial_char = bytes;
+                return Err(e);
+            }
+        };
+        self.text.push_str(valid);
+        Ok(())
+    }
+
+    /// Marks the end of the input. Afterwards, the tokens are no longer streaming, so the last
+    /// token is returned even if it ends at the end of the text.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Returns `true` if [`StreamBuffer::finish`] was called.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the text that was received and not consumed yet.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the tokens in the text that was received and not consumed yet.
+    pub fn tokens<'i, T>(&'i self) -> Tokens<'i, T>
+    where
+        T: Logos<'i, Source = str> + Clone,
+        T::Extras: Default + Clone,
+    {
+        let tokens = Tokens::new(self.text.as_str());
+        if self.finished {
+            tokens
+        } else {
+            tokens.streaming()
+        }
+    }
+
+    /// Removes the first `len` bytes of the text, typically after they were parsed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is not on a character boundary or greater than the length of the text.
+    pub fn consume(&mut self, len: usize) {
+        self.text.drain(..len);
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl StreamBuffer {
+    /// Reads a chunk from `reader` and appends it. Returns the number of bytes read; when it is
+    /// 0, the reader is exhausted and the buffer is [finished](StreamBuffer::finish).
+    pub async fn fill<R>(&mut self, reader: &mut R) -> std::io::Result<usize>
+    where
+        R: futures_io::AsyncRead + Unpin,
+    {
+        let mut chunk = [0; 4096];
+        let n =
+            core::future::poll_fn(|cx| core::pin::Pin::new(&mut *reader).poll_read(cx, &mut chunk))
+                .await?;
+        if n == 0 {
+            self.finish();
+        } else {
+            self.push(&chunk[..n])
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        }
+        Ok(n)
+    }
+}
@@ -0,0 +1,225 @@
+//! Recording of the parser call tree, for understanding how a grammar backtracks.
+//!
+//! Wrap the rules of a grammar with [`trace`], and attach a [`Tracer`] to the input with
+//! [`Tokens::with_tracer`]. Each call of a traced rule is recorded as a [`TraceNode`] with the
+//! positions where it started and ended and whether it succeeded. The calls of traced rules
+//! inside of it become its children. Without a tracer, [`trace`] just calls the parser.
+//!
+//! ### Example
+//!
+//! ```
+//! # #[derive(Clone, Debug, PartialEq, Eq, logos::Logos)]
+//! # #[logos(skip r"[ \t\n\f]+")]
+//! # enum Token {
+//! #     #[token("+")]
+//! #     Plus,
+//! #
+//! #     #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+//! #     Number(i64),
+//! # }
+//! #
+//! use logos_nom_bridge::{
+//!     trace::{trace, Tracer},
+//!     Tokens,
+//! };
+//! use nom::{branch::alt, sequence::separated_pair, IResult, Parser};
+//!
+//! logos_nom_bridge::token_parser!(token: Token);
+//!
+//! logos_nom_bridge::data_variant_parser! {
+//!     fn number(input) -> Result<i64>;
+//!     pattern = Token::Number(n) => n;
+//! }
+//!
+//! type Input<'i> = Tokens<'i, Token>;
+//!
+//! fn expr(input: Input<'_>) -> IResult<Input<'_>, i64> {
+//!     let sum = separated_pair(trace("number", number), Token::Plus, trace("number", number));
+//!     trace(
+//!         "expr",
+//!         alt((
+//!             trace("sum", sum).map(|(a, b)| a + b),
+//!             trace("number", number),
+//!         )),
+//!     )(input)
+//! }
+//!
+//! let tracer = Tracer::new();
+//! let (_, n) = expr(Tokens::new("1 2").with_tracer(tracer.clone())).unwrap();
+//!
+//! assert_eq!(n, 1);
+//! assert_eq!(
+//!     tracer.to_string(),
+//!     "\
+//! expr 0..1 ok
+//!   sum 0 error
+//!     number 0..1 ok
+//!   number 0..1 ok
+//! ",
+//! );
+//! ```
+
+use alloc::{rc::Rc, vec::Vec};
+use core::{cell::RefCell, fmt};
+
+use logos::Logos;
+use nom::{IResult, Parser};
+
+use crate::{TokenSource, Tokens};
+
+/// How a traced parser call ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TraceOutcome {
+    /// The parser succeeded.
+    Ok,
+    /// The parser returned [`nom::Err::Error`].
+    Error,
+    /// The parser returned [`nom::Err::Failure`].
+    Failure,
+    /// The parser returned [`nom::Err::Incomplete`].
+    Incomplete,
+}
+
+/// A recorded call of a traced parser.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceNode {
+    /// The name passed to [`trace`].
+    pub rule: &'static str,
+    /// The byte offset where the parser was called, after the last consumed token.
+    pub start: usize,
+    /// The byte offset after the last token consumed by the parser, if it succeeded.
+    pub end: Option<usize>,
+    /// Whether the parser succeeded.
+    pub outcome: TraceOutcome,
+    /// The calls of traced parsers made by this parser.
+    pub children: Vec<TraceNode>,
+}
+
+#[derive(Default)]
+struct TraceState {
+    roots: Vec<TraceNode>,
+    /// The calls that haven't returned yet, outermost first
+    open: Vec<TraceNode>,
+}
+
+/// A shared collector of traced parser calls. Clones of a `Tracer` record into the same tree.
+///
+/// The `Display` impl prints the recorded calls as an indented tree. See the
+/// [module-level docs](self) for an example.
+#[derive(Clone, Default)]
+pub struct Tracer {
+    state: Rc<RefCell<TraceState>>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Tracer::default()
+    }
+
+    /// Returns the recorded calls of the outermost traced parsers.
+    pub fn roots(&self) -> Vec<TraceNode> {
+        self.state.borrow().roots.clone()
+    }
+
+    /// Removes and returns the recorded calls of the outermost traced parsers.
+    pub fn take(&self) -> Vec<TraceNode> {
+        core::mem::take(&mut self.state.borrow_mut().roots)
+    }
+
+    fn enter(&self, rule: &'static str, start: usize) {
+        self.state.borrow_mut().open.push(TraceNode {
+            rule,
+            start,
+            end: None,
+            outcome: TraceOutcome::Ok,
+            children: Vec::new(),
+        });
+    }
+
+    fn exit(&self, end: Option<usize>, outcome: TraceOutcome) {
+        let mut state = self.state.borrow_mut();
+        let Some(mut node) = state.open.pop() else {
+            return;
+        };
+        node.end = end;
+        node.outcome = outcome;
+        match state.open.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => state.roots.push(node),
+        }
+    }
+}
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Tracer")
+            .field(&self.state.borrow().roots)
+            .finish()
+    }
+}
+
+impl fmt::Display for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.state
+            .borrow()
+            .roots
+            .iter()
+            .try_for_each(|node| node.write_to(f, 0))
+    }
+}
+
+impl TraceNode {
+    fn write_to(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(
+            f,
+            "{:indent$}{} {}",
+            "",
+            self.rule,
+            self.start,
+            indent = depth * 2
+        )?;
+        if let Some(end) = self.end {
+            write!(f, "..{end}")?;
+        }
+        let outcome = match self.outcome {
+            TraceOutcome::Ok => "ok",
+            TraceOutcome::Error => "error",
+            TraceOutcome::Failure => "failure",
+            TraceOutcome::Incomplete => "incomplete",
+        };
+        writeln!(f, " {outcome}")?;
+        self.children
+            .iter()
+            .try_for_each(|child| child.write_to(f, depth + 1))
+    }
+}
+
+/// Runs `parser` and records the call in the [`Tracer`] of the input, if it has one, under the
+/// name `rule`.
+pub fn trace<'i, T, O, E, P>(
+    rule: &'static str,
+    mut parser: P,
+) -> impl FnMut(Tokens<'i, T>) -> IResult<Tokens<'i, T>, O, E>
+where
+    T: Logos<'i> + Clone,
+    T::Source: TokenSource,
+    T::Extras: Clone,
+    P: Parser<Tokens<'i, T>, O, E>,
+{
+    move |input| {
+        let Some(tracer) = input.hooks.tracer.clone() else {
+            return parser.parse(input);
+        };
+        tracer.enter(rule, input.lexer.span().end);
+        let result = parser.parse(input);
+        match &result {
+            Ok((rest, _)) => tracer.exit(Some(rest.lexer.span().end), TraceOutcome::Ok),
+            Err(nom::Err::Error(_)) => tracer.exit(None, TraceOutcome::Error),
+            Err(nom::Err::Failure(_)) => tracer.exit(None, TraceOutcome::Failure),
+            Err(nom::Err::Incomplete(_)) => tracer.exit(None, TraceOutcome::Incomplete),
+        }
+        result
+    }
+}